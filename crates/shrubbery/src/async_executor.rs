@@ -0,0 +1,395 @@
+//! # Leaf nodes driven by [`Future`]s
+//!
+//! An [`AsyncExecutor`] starts a [`Future`] when its leaf is first ticked, the leaf then returns
+//! [`Status::Running`] until the future resolves, at which point the output is folded back into
+//! the [`Blackboard`] to get the final [`Status`].
+//!
+//! Futures are polled through a [`Waker`] that flags an [`AsyncSignal`] shared by the whole
+//! tree, so the caller can tell whether ticking again will make any progress. Shrubbery doesn't
+//! depend on any particular async runtime:
+//!
+//! - [`ShrubberyBT::run_async`] ticks the tree each time the signal fires & can be awaited from
+//!   whatever runtime you are using.
+//! - [`ShrubberyBT::run_blocking`] drives it with the minimal [`block_on`] poll loop, which is
+//!   handy for tests & simple tools.
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+use crate::bt::ShrubberyBT;
+use crate::traits::*;
+use crate::Status;
+
+/// A boxed [`Future`] owned by an [`AsyncLeaf`] while it is running.
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+
+/// Leaf nodes whose task is a [`Future`].
+///
+/// The future can't borrow the [`Blackboard`] since it outlives the tick that started it, so
+/// anything it needs is read in [`Self::start`], and its output is written back in
+/// [`Self::finish`].
+pub trait AsyncExecutor<BB: Blackboard>: Clone + Debug {
+    type Output;
+
+    /// Start the task, called when the leaf is ticked and isn't already running.
+    fn start(&mut self, blackboard: &mut BB) -> BoxFuture<Self::Output>;
+
+    /// The future resolved, update the blackboard & return the status of the leaf.
+    fn finish(&mut self, output: Self::Output, blackboard: &mut BB) -> Status;
+
+    /// The leaf was halted while the future was pending, the future has already been dropped.
+    fn halt(&mut self, _blackboard: &mut BB) {}
+
+    /// Optional name for coloring the leaf nodes in the [`ControlTree`](crate::control::ControlTree)
+    fn name(&self) -> Option<String> {
+        None
+    }
+
+    /// Optional details for coloring the leaf nodes in the [`ControlTree`](crate::control::ControlTree)
+    fn details(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Shared flag that is raised whenever a future owned by one of the tree's [`AsyncLeaf`]s is
+/// woken, i.e. whenever ticking the tree again could make progress.
+#[derive(Debug, Clone, Default)]
+pub struct AsyncSignal(Arc<SignalInner>);
+
+#[derive(Debug, Default)]
+struct SignalInner {
+    ready: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl AsyncSignal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Has anything been woken since the signal was last taken?
+    pub fn is_ready(&self) -> bool {
+        self.0.ready.load(Ordering::Acquire)
+    }
+
+    /// Clear the signal, returning whether it was raised.
+    pub fn take_ready(&self) -> bool {
+        self.0.ready.swap(false, Ordering::AcqRel)
+    }
+
+    /// Raise the signal & wake whoever is waiting on [`Self::ready`].
+    pub fn notify(&self) {
+        self.0.ready.store(true, Ordering::Release);
+        if let Some(waker) = self.0.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+
+    /// Future that resolves (and clears the signal) once the signal is raised.
+    pub fn ready(&self) -> Ready<'_> {
+        Ready(self)
+    }
+}
+
+/// Future returned by [`AsyncSignal::ready`]
+#[derive(Debug)]
+pub struct Ready<'a>(&'a AsyncSignal);
+
+impl Future for Ready<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let signal = &self.0 .0;
+        if self.0.take_ready() {
+            return Poll::Ready(());
+        }
+        *signal.waker.lock().unwrap() = Some(cx.waker().clone());
+        // check again in case the signal was raised before the waker was registered
+        if self.0.take_ready() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Per-leaf waker, remembers that this leaf's future wants polling & raises the tree's signal.
+#[derive(Debug)]
+struct LeafWaker {
+    woken: AtomicBool,
+    signal: AsyncSignal,
+}
+
+impl Wake for LeafWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::Release);
+        self.signal.notify();
+    }
+}
+
+/// Adapts an [`AsyncExecutor`] into an [`Executor`], so it can be dispatched to like any other
+/// leaf.
+///
+/// Cloning an [`AsyncLeaf`] doesn't clone the pending future, the clone starts from scratch.
+pub struct AsyncLeaf<BB: Blackboard, E: AsyncExecutor<BB>> {
+    pub executor: E,
+    waker: Arc<LeafWaker>,
+    future: Option<BoxFuture<E::Output>>,
+}
+
+impl<BB: Blackboard, E: AsyncExecutor<BB>> AsyncLeaf<BB, E> {
+    /// Wrap `executor`, waking `signal` whenever its future wants to be polled.
+    pub fn new(executor: E, signal: &AsyncSignal) -> Self {
+        Self {
+            executor,
+            waker: Arc::new(LeafWaker {
+                woken: AtomicBool::new(false),
+                signal: signal.clone(),
+            }),
+            future: None,
+        }
+    }
+
+    /// Is the future still pending?
+    pub fn is_pending(&self) -> bool {
+        self.future.is_some()
+    }
+
+    fn poll(&mut self, blackboard: &mut BB) -> Status {
+        let Some(future) = self.future.as_mut() else {
+            return self.on_start(blackboard);
+        };
+        self.waker.woken.store(false, Ordering::Release);
+
+        let waker = Waker::from(self.waker.clone());
+        let mut cx = Context::from_waker(&waker);
+        match future.as_mut().poll(&mut cx) {
+            Poll::Pending => Status::Running,
+            Poll::Ready(output) => {
+                self.future = None;
+                self.executor.finish(output, blackboard)
+            }
+        }
+    }
+}
+
+impl<BB: Blackboard, E: AsyncExecutor<BB>> Clone for AsyncLeaf<BB, E> {
+    fn clone(&self) -> Self {
+        Self::new(self.executor.clone(), &self.waker.signal)
+    }
+}
+
+impl<BB: Blackboard, E: AsyncExecutor<BB>> Debug for AsyncLeaf<BB, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncLeaf")
+            .field("executor", &self.executor)
+            .field("pending", &self.is_pending())
+            .finish()
+    }
+}
+
+impl<BB: Blackboard, E: AsyncExecutor<BB>> Executor<BB> for AsyncLeaf<BB, E> {
+    /// Run a fresh copy of the task to completion, blocking the thread until it finishes. The
+    /// tree goes through the lifecycle methods instead, which never block.
+    fn execute(&self, blackboard: &mut BB) -> Status {
+        let mut executor = self.executor.clone();
+        let output = block_on(executor.start(blackboard));
+        executor.finish(output, blackboard)
+    }
+
+    fn on_start(&mut self, blackboard: &mut BB) -> Status {
+        self.future = Some(self.executor.start(blackboard));
+        self.poll(blackboard)
+    }
+
+    fn on_running(&mut self, blackboard: &mut BB) -> Status {
+        if self.future.is_some() && !self.waker.woken.load(Ordering::Acquire) {
+            // nothing has changed since the last poll
+            return Status::Running;
+        }
+        self.poll(blackboard)
+    }
+
+    fn on_halted(&mut self, blackboard: &mut BB) {
+        if self.future.take().is_some() {
+            self.executor.halt(blackboard);
+        }
+    }
+
+    fn name(&self) -> Option<String> {
+        self.executor.name()
+    }
+
+    fn details(&self) -> Option<String> {
+        self.executor.details()
+    }
+}
+
+/// Minimal poll loop, runs `future` to completion on the current thread, parking it while the
+/// future is pending.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    struct ThreadWaker(std::thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let mut future = std::pin::pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+impl<H: ActionHandler, D: Decorator, C: Composite> ShrubberyBT<H, D, C> {
+    /// Tick the tree until it finishes, waiting for `signal` between ticks.
+    ///
+    /// NOTE: Only [`AsyncLeaf`]s raise the signal, if the tree is held up by a running leaf
+    /// that isn't async this will wait forever.
+    pub async fn run_async(&mut self, blackboard: &mut H::Bb, signal: &AsyncSignal) -> Status {
+        loop {
+            let status = self.tick(blackboard);
+            if status.is_terminal() {
+                return status;
+            }
+            signal.ready().await;
+        }
+    }
+
+    /// [`Self::run_async`] on the current thread, using [`block_on`].
+    pub fn run_blocking(&mut self, blackboard: &mut H::Bb, signal: &AsyncSignal) -> Status {
+        block_on(self.run_async(blackboard, signal))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Default, Clone)]
+    struct GateState {
+        value: Option<u32>,
+        waker: Option<Waker>,
+    }
+
+    /// Future that is pending until the gate is opened with a value.
+    #[derive(Debug, Default, Clone)]
+    struct Gate(Arc<Mutex<GateState>>);
+
+    impl Gate {
+        fn open(&self, value: u32) {
+            let mut state = self.0.lock().unwrap();
+            state.value = Some(value);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    impl Future for Gate {
+        type Output = u32;
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> {
+            let mut state = self.0.lock().unwrap();
+            match state.value {
+                Some(value) => Poll::Ready(value),
+                None => {
+                    state.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct Fetch(Gate);
+
+    impl AsyncExecutor<Option<u32>> for Fetch {
+        type Output = u32;
+        fn start(&mut self, _: &mut Option<u32>) -> BoxFuture<u32> {
+            Box::pin(self.0.clone())
+        }
+        fn finish(&mut self, output: u32, blackboard: &mut Option<u32>) -> Status {
+            *blackboard = Some(output);
+            Status::Success
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct NoCondition;
+
+    impl Conditional<Option<u32>> for NoCondition {
+        fn conditional(&self, _: &Option<u32>) -> Status {
+            Status::Success
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct AsyncHandler;
+
+    impl ActionHandler for AsyncHandler {
+        type Bb = Option<u32>;
+        type Execute = AsyncLeaf<Option<u32>, Fetch>;
+        type Condition = NoCondition;
+    }
+
+    fn fetch_bt(gate: &Gate, signal: &AsyncSignal) -> ShrubberyBT<AsyncHandler> {
+        let mut builder = ShrubberyBT::<AsyncHandler>::builder();
+        builder.layer_with_deps(
+            AsyncLeaf::new(Fetch(gate.clone()), signal),
+            |leaf, mut root| {
+                root.execute(leaf);
+            },
+        );
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn pending_until_woken() {
+        let gate = Gate::default();
+        let signal = AsyncSignal::new();
+        let mut bt = fetch_bt(&gate, &signal);
+        let mut bb = None;
+
+        assert_eq!(bt.tick(&mut bb), Status::Running);
+        assert_eq!(bt.tick(&mut bb), Status::Running);
+        assert!(!signal.is_ready());
+
+        gate.open(7);
+        assert!(signal.take_ready());
+        assert_eq!(bt.tick(&mut bb), Status::Success);
+        assert_eq!(bb, Some(7));
+    }
+
+    #[test]
+    fn run_blocking() {
+        let gate = Gate::default();
+        let signal = AsyncSignal::new();
+        let mut bt = fetch_bt(&gate, &signal);
+        let mut bb = None;
+
+        let opener = {
+            let gate = gate.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                gate.open(3);
+            })
+        };
+        assert_eq!(bt.run_blocking(&mut bb, &signal), Status::Success);
+        assert_eq!(bb, Some(3));
+        opener.join().unwrap();
+    }
+}
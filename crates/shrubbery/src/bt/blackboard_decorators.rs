@@ -0,0 +1,650 @@
+//! # [`BlackboardDecorator`] & [`BlackboardSelector`] implementations
+
+use std::fmt::Debug;
+
+use crate::executor_mask::tick_conditional;
+use crate::traits::*;
+use crate::Status;
+
+/// Only tick the branch while a [`Conditional`] succeeds.
+///
+/// The condition is checked every time the branch is about to be ticked, including while it is
+/// running, so a branch that is already going gets halted as soon as the condition fails.
+#[derive(Debug, Clone)]
+pub struct Precondition<C> {
+    pub condition: C,
+
+    /// What to return when the condition fails
+    pub otherwise: PreTick,
+}
+
+impl<C> Precondition<C> {
+    pub fn new(condition: C) -> Self {
+        Self {
+            condition,
+            otherwise: PreTick::Fail,
+        }
+    }
+
+    /// Do `otherwise` instead of failing when the condition fails
+    pub fn otherwise(mut self, otherwise: PreTick) -> Self {
+        self.otherwise = otherwise;
+        self
+    }
+}
+
+impl<BB: Blackboard, C: Conditional<BB> + 'static> BlackboardDecorator<BB> for Precondition<C> {
+    fn pre_tick(&mut self, blackboard: &BB) -> PreTick {
+        // the read-only check is done around `pre_tick` by the `TaskHook`
+        let name = || format!("Precondition {:?}", self.condition);
+        match tick_conditional(&self.condition, name, blackboard, None) {
+            Status::Success => PreTick::Tick,
+            Status::Failure => self.otherwise,
+            // not decided yet, wait for it
+            Status::Running => PreTick::Skip,
+        }
+    }
+
+    fn name(&self) -> Option<String> {
+        self.condition.name().or(Some("Precondition".to_string()))
+    }
+}
+
+/// Roll the blackboard back to how it was when the branch started if the branch fails, so a
+/// half finished branch doesn't leave its writes behind.
+///
+/// The whole blackboard is cloned when the branch starts, see [`UndoTransaction`] for large
+/// blackboards. The writes are kept if the branch is halted.
+///
+/// NOTE: The snapshot is of the *whole* blackboard, so under a
+/// [`Parallel`](crate::control::control_nodes::Parallel) a failing transaction also undoes
+/// whatever its siblings wrote while it was running. Put the parallel node inside the transaction
+/// instead if its branches should succeed or fail together.
+#[derive(Debug, Clone)]
+pub struct Transaction<BB> {
+    snapshot: Option<BB>,
+}
+
+impl<BB> Transaction<BB> {
+    pub fn new() -> Self {
+        Self { snapshot: None }
+    }
+}
+
+impl<BB> Default for Transaction<BB> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<BB: Blackboard + 'static> BlackboardDecorator<BB> for Transaction<BB> {
+    fn pre_tick(&mut self, blackboard: &BB) -> PreTick {
+        if self.snapshot.is_none() {
+            self.snapshot = Some(blackboard.clone());
+        }
+        PreTick::Tick
+    }
+
+    fn post_tick(&mut self, blackboard: &mut BB, status: Status) {
+        match status {
+            Status::Running => {}
+            Status::Success => self.snapshot = None,
+            Status::Failure => {
+                if let Some(snapshot) = self.snapshot.take() {
+                    *blackboard = snapshot;
+                }
+            }
+        }
+    }
+
+    fn halt(&mut self, _blackboard: &mut BB) {
+        self.snapshot = None;
+    }
+
+    fn name(&self) -> Option<String> {
+        Some("Transaction".to_string())
+    }
+}
+
+/// A [`Blackboard`] that records its own changes, so they can be undone without a snapshot of
+/// the whole thing, see [`UndoTransaction`].
+///
+/// Checkpoints are nested like the branches they are taken for, the last one taken is always the
+/// first one to be rolled back or committed.
+pub trait UndoLog: Blackboard {
+    type Checkpoint: Debug + Clone;
+
+    /// Start recording changes
+    fn checkpoint(&mut self) -> Self::Checkpoint;
+
+    /// Undo every change since `checkpoint`
+    fn rollback(&mut self, checkpoint: Self::Checkpoint);
+
+    /// Keep the changes since `checkpoint`, they can still be undone by an earlier checkpoint
+    fn commit(&mut self, checkpoint: Self::Checkpoint);
+}
+
+/// [`Transaction`] for an [`UndoLog`] blackboard, only the changes made by the branch are undone
+/// when it fails.
+///
+/// NOTE: [`UndoLog`] checkpoints have to nest, which branches running side by side under a
+/// [`Parallel`](crate::control::control_nodes::Parallel) don't: rolling one of them back also
+/// undoes what its siblings wrote since it started, like [`Transaction`].
+#[derive(Debug, Clone)]
+pub struct UndoTransaction<BB: UndoLog> {
+    checkpoint: Option<BB::Checkpoint>,
+}
+
+impl<BB: UndoLog> UndoTransaction<BB> {
+    pub fn new() -> Self {
+        Self { checkpoint: None }
+    }
+}
+
+impl<BB: UndoLog> Default for UndoTransaction<BB> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<BB: UndoLog + 'static> BlackboardDecorator<BB> for UndoTransaction<BB> {
+    fn pre_tick(&mut self, _blackboard: &BB) -> PreTick {
+        PreTick::Tick
+    }
+
+    fn before_tick(&mut self, blackboard: &mut BB) {
+        if self.checkpoint.is_none() {
+            self.checkpoint = Some(blackboard.checkpoint());
+        }
+    }
+
+    fn post_tick(&mut self, blackboard: &mut BB, status: Status) {
+        let Some(checkpoint) = self.checkpoint.take_if(|_| status.is_terminal()) else {
+            return;
+        };
+        match status {
+            Status::Failure => blackboard.rollback(checkpoint),
+            _ => blackboard.commit(checkpoint),
+        }
+    }
+
+    fn halt(&mut self, blackboard: &mut BB) {
+        if let Some(checkpoint) = self.checkpoint.take() {
+            blackboard.commit(checkpoint);
+        }
+    }
+
+    fn name(&self) -> Option<String> {
+        Some("Transaction".to_string())
+    }
+}
+
+/// Picks the case of a [`Switch`](crate::control::control_nodes::Switch) matching a key read from
+/// the blackboard, or the default case if none of them match.
+#[derive(Debug, Clone)]
+pub struct SwitchCases<BB, K> {
+    pub key: fn(&BB) -> K,
+
+    /// The key of each child, `None` for the default
+    pub cases: Vec<Option<K>>,
+}
+
+impl<BB, K> SwitchCases<BB, K> {
+    pub fn new(key: fn(&BB) -> K, cases: Vec<Option<K>>) -> Self {
+        Self { key, cases }
+    }
+}
+
+impl<BB, K> BlackboardSelector<BB> for SwitchCases<BB, K>
+where
+    BB: Blackboard + 'static,
+    K: PartialEq + Clone + Debug + 'static,
+{
+    fn select(&mut self, blackboard: &BB, _children: usize) -> Selection {
+        let key = Some((self.key)(blackboard));
+        let case = self
+            .cases
+            .iter()
+            .position(|case| *case == key)
+            .or_else(|| self.cases.iter().position(Option::is_none));
+        Selection::ordered(case.into_iter().collect())
+    }
+}
+
+/// Scores the children of a [`UtilitySelector`](crate::control::control_nodes::UtilitySelector)
+/// from the blackboard.
+#[derive(Debug, Clone)]
+pub struct UtilityScores<BB> {
+    /// The scoring function of each child
+    pub scorers: Vec<fn(&BB) -> f32>,
+}
+
+impl<BB> UtilityScores<BB> {
+    pub fn new(scorers: Vec<fn(&BB) -> f32>) -> Self {
+        Self { scorers }
+    }
+}
+
+impl<BB: Blackboard + 'static> BlackboardSelector<BB> for UtilityScores<BB> {
+    fn select(&mut self, blackboard: &BB, _children: usize) -> Selection {
+        Selection::scored(self.scorers.iter().map(|score| score(blackboard)).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bt::ShrubberyBT;
+    use crate::control::control_nodes::{Memory, ParallelPolicy};
+    use crate::control::decorators::StandardDecorator;
+    use crate::control::{CTreeNodeID, ControlTree, ROOT_ID};
+
+    #[derive(Debug, Default, Clone)]
+    struct Robot {
+        battery: u32,
+        halted: bool,
+        mode: Mode,
+        acted: Vec<Mode>,
+    }
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    enum Mode {
+        #[default]
+        Idle,
+        Patrol,
+        Combat,
+    }
+
+    #[derive(Debug, Clone)]
+    struct BatteryAbove(u32);
+
+    impl Conditional<Robot> for BatteryAbove {
+        fn conditional(&self, robot: &Robot) -> Status {
+            (robot.battery > self.0).into()
+        }
+    }
+
+    /// Drains the battery, never arrives.
+    #[derive(Debug, Clone)]
+    struct Drive;
+
+    impl Executor<Robot> for Drive {
+        fn execute(&self, robot: &mut Robot) -> Status {
+            robot.battery -= 10;
+            Status::Running
+        }
+        fn halt(&self, robot: &mut Robot) {
+            robot.halted = true;
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct RobotHandler;
+
+    impl ActionHandler for RobotHandler {
+        type Bb = Robot;
+        type Execute = Drive;
+        type Condition = BatteryAbove;
+    }
+
+    #[test]
+    fn precondition() {
+        let mut builder = ShrubberyBT::<RobotHandler>::builder();
+        builder.layer(|mut root| {
+            root.precondition(BatteryAbove(20), |mut guarded| {
+                guarded.execute(Drive);
+            });
+        });
+        let mut bt = builder.build().unwrap();
+        let mut robot = Robot {
+            battery: 40,
+            ..Default::default()
+        };
+
+        assert_eq!(bt.tick(&mut robot), Status::Running);
+        assert_eq!(bt.tick(&mut robot), Status::Running);
+        assert_eq!(robot.battery, 20);
+        assert!(!robot.halted);
+
+        // the condition fails while the branch is running, it's halted without another tick
+        assert_eq!(bt.tick(&mut robot), Status::Failure);
+        assert_eq!(robot.battery, 20);
+        assert!(robot.halted);
+
+        // and it isn't started again
+        assert_eq!(bt.tick(&mut robot), Status::Failure);
+        assert_eq!(robot.battery, 20);
+    }
+
+    #[test]
+    fn precondition_otherwise() {
+        let mut builder = ShrubberyBT::<RobotHandler>::builder();
+        builder.layer(|mut root| {
+            let skip = Precondition::new(BatteryAbove(20)).otherwise(PreTick::Skip);
+            root.blackboard_decorator(skip, |mut guarded| {
+                guarded.execute(Drive);
+            });
+        });
+        let mut bt = builder.build().unwrap();
+        let mut robot = Robot {
+            battery: 10,
+            ..Default::default()
+        };
+
+        assert_eq!(bt.tick(&mut robot), Status::Running);
+        assert_eq!(robot.battery, 10);
+
+        robot.battery = 30;
+        assert_eq!(bt.tick(&mut robot), Status::Running);
+        assert_eq!(robot.battery, 20);
+    }
+
+    /// Spends battery, fails if there isn't enough left.
+    #[derive(Debug, Clone)]
+    struct Spend(u32);
+
+    impl Executor<Robot> for Spend {
+        fn execute(&self, robot: &mut Robot) -> Status {
+            match robot.battery.checked_sub(self.0) {
+                Some(battery) => {
+                    robot.battery = battery;
+                    Status::Success
+                }
+                None => Status::Failure,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct SpendHandler;
+
+    impl ActionHandler for SpendHandler {
+        type Bb = Robot;
+        type Execute = Spend;
+        type Condition = BatteryAbove;
+    }
+
+    #[test]
+    fn transaction() {
+        let mut builder = ShrubberyBT::<SpendHandler>::builder();
+        builder.layer(|mut root| {
+            root.fallback(|mut fallback| {
+                fallback.transaction(|mut expensive| {
+                    expensive.execute(Spend(30));
+                    expensive.execute(Spend(30));
+                });
+                fallback.execute(Spend(5));
+            });
+        });
+        let mut bt = builder.build().unwrap();
+
+        // the first spend is rolled back when the second fails
+        let mut robot = Robot {
+            battery: 40,
+            ..Default::default()
+        };
+        assert_eq!(bt.tick(&mut robot), Status::Success);
+        assert_eq!(robot.battery, 35);
+
+        bt.reset(&mut robot);
+        let mut robot = Robot {
+            battery: 100,
+            ..Default::default()
+        };
+        assert_eq!(bt.tick(&mut robot), Status::Success);
+        assert_eq!(robot.battery, 40);
+    }
+
+    #[test]
+    fn transaction_reset() {
+        let mut builder = ShrubberyBT::<RobotHandler>::builder();
+        builder.layer(|mut root| {
+            root.transaction(|mut drive| {
+                drive.condition(BatteryAbove(25));
+                drive.execute(Drive);
+            });
+        });
+        let mut bt = builder.build().unwrap();
+        let mut robot = Robot {
+            battery: 40,
+            ..Default::default()
+        };
+        assert_eq!(bt.tick(&mut robot), Status::Running);
+        assert_eq!(robot.battery, 30);
+
+        // the snapshot from before the reset isn't restored when the branch fails
+        bt.reset(&mut robot);
+        assert!(robot.halted);
+        robot.battery = 20;
+        assert_eq!(bt.tick(&mut robot), Status::Failure);
+        assert_eq!(robot.battery, 20);
+    }
+
+    #[test]
+    fn transaction_in_parallel() {
+        let mut builder = ShrubberyBT::<RobotHandler>::builder();
+        builder.layer(|mut root| {
+            let fail_on_one = ParallelPolicy::threshold(None, Some(1));
+            root.parallel_policy(fail_on_one, |mut both| {
+                both.execute(Drive);
+                both.transaction(|mut drive| {
+                    drive.memory(Memory::ReevaluateConditionsOnly);
+                    drive.condition(BatteryAbove(25));
+                    drive.execute(Drive);
+                });
+            });
+        });
+        let mut bt = builder.build().unwrap();
+        let mut robot = Robot {
+            battery: 40,
+            ..Default::default()
+        };
+        assert_eq!(bt.tick(&mut robot), Status::Running);
+        assert_eq!(robot.battery, 20);
+
+        // the sibling drained the battery to 10 before the transaction failed, that's rolled back
+        // along with the transaction's own drive
+        assert_eq!(bt.tick(&mut robot), Status::Failure);
+        assert_eq!(robot.battery, 30);
+        assert!(robot.halted);
+    }
+
+    /// Keeps acting out a mode, never finishes.
+    #[derive(Debug, Clone)]
+    struct Act(Mode);
+
+    impl Executor<Robot> for Act {
+        fn execute(&self, robot: &mut Robot) -> Status {
+            robot.acted.push(self.0);
+            Status::Running
+        }
+        fn halt(&self, robot: &mut Robot) {
+            robot.halted = true;
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct ModeHandler;
+
+    impl ActionHandler for ModeHandler {
+        type Bb = Robot;
+        type Execute = Act;
+        type Condition = BatteryAbove;
+    }
+
+    #[test]
+    fn switch() {
+        let mut builder = ShrubberyBT::<ModeHandler>::builder();
+        builder.layer(|mut root| {
+            root.switch(
+                |robot| robot.mode,
+                |mut cases| {
+                    cases.case(Mode::Patrol, |mut patrol| {
+                        patrol.execute(Act(Mode::Patrol));
+                    });
+                    cases.case(Mode::Combat, |mut combat| {
+                        combat.execute(Act(Mode::Combat));
+                    });
+                    cases.default(|mut idle| {
+                        idle.execute(Act(Mode::Idle));
+                    });
+                },
+            );
+        });
+        let mut bt = builder.build().unwrap();
+        let mut robot = Robot::default();
+
+        assert_eq!(bt.tick(&mut robot), Status::Running);
+        assert_eq!(robot.acted, vec![Mode::Idle]);
+
+        // the key changes, the idle branch is halted & only the combat branch runs
+        robot.mode = Mode::Combat;
+        assert_eq!(bt.tick(&mut robot), Status::Running);
+        assert_eq!(bt.tick(&mut robot), Status::Running);
+        assert_eq!(robot.acted, vec![Mode::Idle, Mode::Combat, Mode::Combat]);
+        assert!(robot.halted);
+    }
+
+    #[test]
+    fn switch_without_default() {
+        let mut builder = ShrubberyBT::<ModeHandler>::builder();
+        builder.layer(|mut root| {
+            root.switch(
+                |robot| robot.mode,
+                |mut cases| {
+                    cases.case(Mode::Patrol, |mut patrol| {
+                        patrol.execute(Act(Mode::Patrol));
+                    });
+                },
+            );
+        });
+        let mut bt = builder.build().unwrap();
+        let mut robot = Robot::default();
+
+        assert_eq!(bt.tick(&mut robot), Status::Failure);
+        assert!(robot.acted.is_empty());
+    }
+
+    fn combat_score(robot: &Robot) -> f32 {
+        robot.battery as f32 / 100.0
+    }
+
+    /// Keeps the scores handed to the update callback
+    #[derive(Default)]
+    struct Scored(Vec<(CTreeNodeID, Vec<f32>)>);
+
+    impl UpdateCallback<StandardDecorator> for Scored {
+        fn callback(&mut self, _state: &ControlTree<StandardDecorator>) {}
+        fn scores(&mut self, node_id: CTreeNodeID, scores: &[f32]) {
+            self.0.push((node_id, scores.to_vec()));
+        }
+    }
+
+    #[test]
+    fn utility_selector() {
+        let mut builder = ShrubberyBT::<ModeHandler>::builder();
+        builder.layer(|mut root| {
+            root.utility_selector(None, |mut options| {
+                options.option(combat_score, |mut combat| {
+                    combat.condition(BatteryAbove(50));
+                    combat.execute(Act(Mode::Combat));
+                });
+                options.option(
+                    |_| 0.4,
+                    |mut patrol| {
+                        patrol.execute(Act(Mode::Patrol));
+                    },
+                );
+            });
+        });
+        let mut bt = builder.build().unwrap();
+        let mut robot = Robot {
+            battery: 60,
+            ..Default::default()
+        };
+
+        let mut scored = Scored::default();
+        assert_eq!(
+            bt.tick_with_update_callback(&mut robot, &mut scored),
+            Status::Running
+        );
+        assert_eq!(robot.acted, vec![Mode::Combat]);
+        let utility = bt.control_tree.children(&ROOT_ID)[0];
+        assert_eq!(scored.0, vec![(utility, vec![0.6, 0.4])]);
+        let scores = bt.control_tree[utility]
+            .try_as_control()
+            .and_then(|c| c.try_as_composite())
+            .and_then(|c| c.details());
+        assert_eq!(scores, Some("scores [0.6, 0.4]".to_string()));
+
+        // the scores aren't looked at again while it's running
+        robot.battery = 10;
+        assert_eq!(bt.tick(&mut robot), Status::Running);
+        assert_eq!(robot.acted, vec![Mode::Combat, Mode::Combat]);
+    }
+
+    #[test]
+    fn utility_selector_next_best() {
+        let mut builder = ShrubberyBT::<ModeHandler>::builder();
+        builder.layer(|mut root| {
+            root.utility_selector(None, |mut options| {
+                options.option(combat_score, |mut combat| {
+                    combat.condition(BatteryAbove(50));
+                    combat.execute(Act(Mode::Combat));
+                });
+                options.option(
+                    |_| 0.4,
+                    |mut patrol| {
+                        patrol.execute(Act(Mode::Patrol));
+                    },
+                );
+            });
+        });
+        let mut bt = builder.build().unwrap();
+        let mut robot = Robot {
+            battery: 45,
+            ..Default::default()
+        };
+
+        // combat scores best but fails, patrol runs in the same tick
+        assert_eq!(bt.tick(&mut robot), Status::Running);
+        assert_eq!(robot.acted, vec![Mode::Patrol]);
+    }
+
+    #[test]
+    fn utility_selector_rescore() {
+        let mut builder = ShrubberyBT::<ModeHandler>::builder();
+        builder.layer(|mut root| {
+            root.utility_selector(Some(0.2), |mut options| {
+                options.option(combat_score, |mut combat| {
+                    combat.execute(Act(Mode::Combat));
+                });
+                options.option(
+                    |_| 0.4,
+                    |mut patrol| {
+                        patrol.execute(Act(Mode::Patrol));
+                    },
+                );
+            });
+        });
+        let mut bt = builder.build().unwrap();
+        let mut robot = Robot {
+            battery: 50,
+            ..Default::default()
+        };
+
+        assert_eq!(bt.tick(&mut robot), Status::Running);
+        assert_eq!(robot.acted, vec![Mode::Combat]);
+
+        // patrol scores better, but not by enough to switch
+        robot.battery = 30;
+        assert_eq!(bt.tick(&mut robot), Status::Running);
+        assert_eq!(robot.acted, vec![Mode::Combat, Mode::Combat]);
+        assert!(!robot.halted);
+
+        robot.battery = 10;
+        assert_eq!(bt.tick(&mut robot), Status::Running);
+        assert_eq!(robot.acted, vec![Mode::Combat, Mode::Combat, Mode::Patrol]);
+        assert!(robot.halted);
+    }
+}
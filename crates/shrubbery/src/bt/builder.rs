@@ -1,22 +1,37 @@
+use std::fmt::Debug;
+use std::time::Duration;
+
+use crate::bt::lens::{Lens, Lensed};
 use crate::bt::ShrubberyBT;
+use crate::clock::ClockHandle;
 use crate::control::builder::{CTreeBuilder, CTreeLayerBuilder};
 use crate::control::{CTreeNodeID, LeafNode, ROOT_ID};
 use crate::executor_mask::LeafDispatch;
-use crate::prelude::ControlNode;
+use crate::kv_blackboard::{KvBlackboard, Port};
+use crate::prelude::{
+    Backoff, ControlNode, Memory, ParallelPolicy, Precondition, RunOnce, StandardComposite,
+    SwitchCases, Transaction, UndoLog, UndoTransaction, UtilityScores,
+};
+use crate::rng::RngHandle;
 use crate::ShrubberyResult;
 
 use super::*;
 
-pub type BTLayerFn<'a, O, H, D> = fn(BTLayer<H, D>) -> O;
-pub type BTLayerFnWithDeps<'a, Deps, O, H, D> = fn(Deps, BTLayer<H, D>) -> O;
+pub type BTLayerFn<'a, O, H, D, C = StandardComposite> = fn(BTLayer<H, D, C>) -> O;
+pub type BTLayerFnWithDeps<'a, Deps, O, H, D, C = StandardComposite> =
+    fn(Deps, BTLayer<H, D, C>) -> O;
 
 /// For building control trees
-pub struct BTBuilder<H: ActionHandler, D: Decorator = StandardDecorator> {
-    inner: CTreeBuilder<D>,
+pub struct BTBuilder<
+    H: ActionHandler,
+    D: Decorator = StandardDecorator,
+    C: Composite = StandardComposite,
+> {
+    inner: CTreeBuilder<D, C>,
     dispatch: LeafDispatch<H>,
 }
 
-impl<H: ActionHandler, D: Decorator> BTBuilder<H, D> {
+impl<H: ActionHandler, D: Decorator, C: Composite> BTBuilder<H, D, C> {
     pub fn new() -> Self {
         Self {
             inner: CTreeBuilder::new(),
@@ -27,7 +42,7 @@ impl<H: ActionHandler, D: Decorator> BTBuilder<H, D> {
     pub fn layer_with_deps<Deps, O>(
         &mut self,
         deps: Deps,
-        f: BTLayerFnWithDeps<Deps, O, H, D>,
+        f: BTLayerFnWithDeps<Deps, O, H, D, C>,
     ) -> O {
         let BTBuilder { inner, dispatch } = self;
 
@@ -39,7 +54,7 @@ impl<H: ActionHandler, D: Decorator> BTBuilder<H, D> {
             },
         )
     }
-    pub fn layer<O>(&mut self, f: BTLayerFn<O, H, D>) -> O {
+    pub fn layer<O>(&mut self, f: BTLayerFn<O, H, D, C>) -> O {
         let BTBuilder { inner, dispatch } = self;
 
         f(BTLayer {
@@ -48,6 +63,26 @@ impl<H: ActionHandler, D: Decorator> BTBuilder<H, D> {
         })
     }
 
+    /// The caller sets `key` on the blackboard before the tree runs, so leaves can read it
+    /// without an earlier leaf writing it, see [`Ports`](crate::kv_blackboard::Ports)
+    pub fn preset(&mut self, key: impl Into<Port>) {
+        self.dispatch.add_preset(key.into());
+    }
+
+    /// [`Self::preset`] every key that is already set on `blackboard`
+    pub fn preset_blackboard(&mut self, blackboard: &KvBlackboard) {
+        blackboard.ports().for_each(|port| self.preset(port));
+    }
+
+    /// Check conditionals don't change the blackboard in debug builds, see
+    /// [`LeafDispatch::check_read_only`]
+    pub fn check_read_only(&mut self)
+    where
+        H::Bb: PartialEq,
+    {
+        self.dispatch.check_read_only();
+    }
+
     /// Build the [`ShrubberyBT`]
     ///
     /// # Errors
@@ -55,13 +90,16 @@ impl<H: ActionHandler, D: Decorator> BTBuilder<H, D> {
     /// - If a cycle is detected in the tree
     /// - If a control node is left dangling (missing leaf)
     /// - If a decorator has more than one child
-    pub fn build(self) -> ShrubberyResult<ShrubberyBT<H, D>> {
+    /// - If a composite rejects its children
+    /// - If a leaf has an input port that isn't written by an earlier leaf or [preset](Self::preset)
+    pub fn build(self) -> ShrubberyResult<ShrubberyBT<H, D, C>> {
         // validate the tree
         let control_tree = self.inner.build()?;
+        self.dispatch.check_ports(&control_tree)?;
 
         Ok(ShrubberyBT {
             control_tree,
-            dispatch: self.dispatch.into(),
+            dispatch: self.dispatch,
         })
     }
 
@@ -73,8 +111,10 @@ impl<H: ActionHandler, D: Decorator> BTBuilder<H, D> {
     }
 }
 
-impl<H: ActionHandler, D: Decorator> From<ShrubberyBT<H, D>> for BTBuilder<H, D> {
-    fn from(value: ShrubberyBT<H, D>) -> Self {
+impl<H: ActionHandler, D: Decorator, C: Composite> From<ShrubberyBT<H, D, C>>
+    for BTBuilder<H, D, C>
+{
+    fn from(value: ShrubberyBT<H, D, C>) -> Self {
         BTBuilder {
             inner: value.control_tree.into_builder(),
             dispatch: value.dispatch,
@@ -82,7 +122,7 @@ impl<H: ActionHandler, D: Decorator> From<ShrubberyBT<H, D>> for BTBuilder<H, D>
     }
 }
 
-impl<H: ActionHandler, D: Decorator> Default for BTBuilder<H, D> {
+impl<H: ActionHandler, D: Decorator, C: Composite> Default for BTBuilder<H, D, C> {
     fn default() -> Self {
         Self::new()
     }
@@ -90,12 +130,27 @@ impl<H: ActionHandler, D: Decorator> Default for BTBuilder<H, D> {
 
 /* -- Layer builder -- */
 
-pub struct BTLayer<'a, H: ActionHandler, D: Decorator = StandardDecorator> {
-    control: CTreeLayerBuilder<'a, D>,
+pub struct BTLayer<
+    'a,
+    H: ActionHandler,
+    D: Decorator = StandardDecorator,
+    C: Composite = StandardComposite,
+> {
+    control: CTreeLayerBuilder<'a, D, C>,
     dispatch: &'a mut LeafDispatch<H>,
 }
 
-impl<'a, H: ActionHandler, D: Decorator> BTLayer<'a, H, D> {
+impl<'a, H: ActionHandler, D: Decorator, C: Composite> BTLayer<'a, H, D, C> {
+    /// The id of the node this layer is adding children to.
+    pub fn layer_id(&self) -> CTreeNodeID {
+        self.control.layer_id
+    }
+
+    /// Set the [`Memory`] of the node this layer is adding children to
+    pub fn memory(&mut self, memory: Memory) {
+        self.control.memory(memory);
+    }
+
     /// Do something to the layer
     pub fn map<O>(self, f: fn(Self) -> O) -> O {
         f(self)
@@ -129,84 +184,553 @@ impl<'a, H: ActionHandler, D: Decorator> BTLayer<'a, H, D> {
         id
     }
 
-    pub fn sequence<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D>) -> O {
+    pub fn composite<O>(
+        &mut self,
+        composite: impl Into<C>,
+        layer_fn: BTLayerFn<'_, O, H, D, C>,
+    ) -> O {
+        let node = ControlNode::composite(composite.into());
+        self.control_node(node, layer_fn)
+    }
+    pub fn composite_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        composite: impl Into<C>,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        let node = ControlNode::composite(composite.into());
+        self.control_node_with_deps(deps, node, layer_fn)
+    }
+
+    pub fn decorator<O>(
+        &mut self,
+        decorator: impl Into<D>,
+        layer_fn: BTLayerFn<'_, O, H, D, C>,
+    ) -> O {
+        let node = ControlNode::decorator(decorator.into());
+        self.control_node(node, layer_fn)
+    }
+    pub fn decorator_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        decorator: impl Into<D>,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        let node = ControlNode::decorator(decorator.into());
+        self.control_node_with_deps(deps, node, layer_fn)
+    }
+
+    pub fn control_node<O>(
+        &mut self,
+        node: impl Into<ControlNode<D, C>>,
+        layer_fn: BTLayerFn<'_, O, H, D, C>,
+    ) -> O {
+        let next_layer = self.control.next_layer(node);
+        layer_fn(BTLayer {
+            control: next_layer,
+            dispatch: self.dispatch,
+        })
+    }
+
+    pub fn control_node_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        node: impl Into<ControlNode<D, C>>,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        let next_layer = self.control.next_layer(node);
+        layer_fn(
+            deps,
+            BTLayer {
+                control: next_layer,
+                dispatch: self.dispatch,
+            },
+        )
+    }
+}
+
+impl<'a, H: ActionHandler, D: Decorator, C: Composite + From<StandardComposite>>
+    BTLayer<'a, H, D, C>
+{
+    pub fn sequence<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
         self.control_node(ControlNode::sequence(), layer_fn)
     }
     pub fn sequence_with_deps<Deps, O>(
         &mut self,
         deps: Deps,
-        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D>,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
     ) -> O {
         self.control_node_with_deps(deps, ControlNode::sequence(), layer_fn)
     }
 
-    pub fn fallback<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D>) -> O {
+    pub fn fallback<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
         self.control_node(ControlNode::fallback(), layer_fn)
     }
     pub fn fallback_with_deps<Deps, O>(
         &mut self,
         deps: Deps,
-        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D>,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
     ) -> O {
         self.control_node_with_deps(deps, ControlNode::fallback(), layer_fn)
     }
 
-    pub fn parallel<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D>) -> O {
+    pub fn parallel<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
         self.control_node(ControlNode::parallel(), layer_fn)
     }
     pub fn parallel_with_deps<Deps, O>(
         &mut self,
         deps: Deps,
-        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D>,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
     ) -> O {
         self.control_node_with_deps(deps, ControlNode::parallel(), layer_fn)
     }
 
-    pub fn decorator<O>(&mut self, decorator: impl Into<D>, layer_fn: BTLayerFn<'_, O, H, D>) -> O {
-        let node = ControlNode::decorator(decorator.into());
-        self.control_node(node, layer_fn)
+    pub fn parallel_policy<O>(
+        &mut self,
+        policy: ParallelPolicy,
+        layer_fn: BTLayerFn<'_, O, H, D, C>,
+    ) -> O {
+        self.control_node(ControlNode::parallel_with_policy(policy), layer_fn)
     }
-    pub fn decorator_with_deps<Deps, O>(
+    pub fn parallel_policy_with_deps<Deps, O>(
         &mut self,
         deps: Deps,
-        decorator: impl Into<D>,
-        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D>,
+        policy: ParallelPolicy,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
     ) -> O {
-        let node = ControlNode::decorator(decorator.into());
-        self.control_node_with_deps(deps, node, layer_fn)
+        self.control_node_with_deps(deps, ControlNode::parallel_with_policy(policy), layer_fn)
     }
 
-    pub fn control_node<O>(
+    pub fn parallel_concurrent<O>(
         &mut self,
-        node: impl Into<ControlNode<D>>,
-        layer_fn: BTLayerFn<'_, O, H, D>,
+        policy: ParallelPolicy,
+        layer_fn: BTLayerFn<'_, O, H, D, C>,
     ) -> O {
-        let next_layer = self.control.next_layer(node);
+        self.control_node(ControlNode::parallel_concurrent(policy), layer_fn)
+    }
+    pub fn parallel_concurrent_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        policy: ParallelPolicy,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        self.control_node_with_deps(deps, ControlNode::parallel_concurrent(policy), layer_fn)
+    }
+
+    pub fn reactive_sequence<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
+        self.control_node(ControlNode::reactive_sequence(), layer_fn)
+    }
+    pub fn reactive_sequence_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        self.control_node_with_deps(deps, ControlNode::reactive_sequence(), layer_fn)
+    }
+
+    pub fn reactive_fallback<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
+        self.control_node(ControlNode::reactive_fallback(), layer_fn)
+    }
+    pub fn reactive_fallback_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        self.control_node_with_deps(deps, ControlNode::reactive_fallback(), layer_fn)
+    }
+
+    /// Add an [`IfThenElse`](crate::control::control_nodes::IfThenElse), the first node added to
+    /// the layer is the condition, then the then & else branches
+    pub fn if_then_else<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
+        self.control_node(ControlNode::if_then_else(), layer_fn)
+    }
+    pub fn if_then_else_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        self.control_node_with_deps(deps, ControlNode::if_then_else(), layer_fn)
+    }
+
+    /// Add a [`WhileDoElse`](crate::control::control_nodes::WhileDoElse), the first node added to
+    /// the layer is the condition, then the do & else branches
+    pub fn while_do_else<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
+        self.control_node(ControlNode::while_do_else(), layer_fn)
+    }
+    pub fn while_do_else_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        self.control_node_with_deps(deps, ControlNode::while_do_else(), layer_fn)
+    }
+
+    pub fn random_selector<O>(
+        &mut self,
+        rng: impl Into<RngHandle>,
+        layer_fn: BTLayerFn<'_, O, H, D, C>,
+    ) -> O {
+        self.control_node(ControlNode::random_selector(rng), layer_fn)
+    }
+    pub fn random_selector_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        rng: impl Into<RngHandle>,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        self.control_node_with_deps(deps, ControlNode::random_selector(rng), layer_fn)
+    }
+
+    pub fn weighted_random<O>(
+        &mut self,
+        weights: Vec<u32>,
+        rng: impl Into<RngHandle>,
+        layer_fn: BTLayerFn<'_, O, H, D, C>,
+    ) -> O {
+        self.control_node(ControlNode::weighted_random(weights, rng), layer_fn)
+    }
+    pub fn weighted_random_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        weights: Vec<u32>,
+        rng: impl Into<RngHandle>,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        self.control_node_with_deps(deps, ControlNode::weighted_random(weights, rng), layer_fn)
+    }
+
+    /// Add a [`Switch`](crate::control::control_nodes::Switch) that runs the case matching the
+    /// `key` read from the blackboard, with the cases declared through the [`BTSwitch`] given to
+    /// `cases_fn`
+    pub fn switch<K, O>(
+        &mut self,
+        key: fn(&H::Bb) -> K,
+        cases_fn: fn(BTSwitch<'_, H, D, C, K>) -> O,
+    ) -> O
+    where
+        H::Bb: 'static,
+        K: PartialEq + Clone + Debug + 'static,
+    {
+        let mut cases = vec![];
+        let next_layer = self.control.next_layer(ControlNode::switch());
+        let switch_id = next_layer.layer_id;
+        let out = cases_fn(BTSwitch {
+            layer: BTLayer {
+                control: next_layer,
+                dispatch: self.dispatch,
+            },
+            cases: &mut cases,
+        });
+        self.dispatch
+            .add_blackboard_selector(switch_id, SwitchCases::new(key, cases));
+        out
+    }
+    pub fn switch_with_deps<K, Deps, O>(
+        &mut self,
+        deps: Deps,
+        key: fn(&H::Bb) -> K,
+        cases_fn: fn(Deps, BTSwitch<'_, H, D, C, K>) -> O,
+    ) -> O
+    where
+        H::Bb: 'static,
+        K: PartialEq + Clone + Debug + 'static,
+    {
+        let mut cases = vec![];
+        let next_layer = self.control.next_layer(ControlNode::switch());
+        let switch_id = next_layer.layer_id;
+        let out = cases_fn(
+            deps,
+            BTSwitch {
+                layer: BTLayer {
+                    control: next_layer,
+                    dispatch: self.dispatch,
+                },
+                cases: &mut cases,
+            },
+        );
+        self.dispatch
+            .add_blackboard_selector(switch_id, SwitchCases::new(key, cases));
+        out
+    }
+
+    /// Add a [`UtilitySelector`](crate::control::control_nodes::UtilitySelector) that runs the
+    /// best scoring option, with the options declared through the [`BTUtility`] given to
+    /// `options_fn`. With `rescore` set the options are re-scored every tick, switching when one
+    /// beats the current option by more than the given hysteresis.
+    pub fn utility_selector<O>(
+        &mut self,
+        rescore: Option<f32>,
+        options_fn: fn(BTUtility<'_, H, D, C>) -> O,
+    ) -> O
+    where
+        H::Bb: 'static,
+    {
+        let mut scorers = vec![];
+        let next_layer = self
+            .control
+            .next_layer(ControlNode::utility_selector(rescore));
+        let utility_id = next_layer.layer_id;
+        let out = options_fn(BTUtility {
+            layer: BTLayer {
+                control: next_layer,
+                dispatch: self.dispatch,
+            },
+            scorers: &mut scorers,
+        });
+        self.dispatch
+            .add_blackboard_selector(utility_id, UtilityScores::new(scorers));
+        out
+    }
+    pub fn utility_selector_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        rescore: Option<f32>,
+        options_fn: fn(Deps, BTUtility<'_, H, D, C>) -> O,
+    ) -> O
+    where
+        H::Bb: 'static,
+    {
+        let mut scorers = vec![];
+        let next_layer = self
+            .control
+            .next_layer(ControlNode::utility_selector(rescore));
+        let utility_id = next_layer.layer_id;
+        let out = options_fn(
+            deps,
+            BTUtility {
+                layer: BTLayer {
+                    control: next_layer,
+                    dispatch: self.dispatch,
+                },
+                scorers: &mut scorers,
+            },
+        );
+        self.dispatch
+            .add_blackboard_selector(utility_id, UtilityScores::new(scorers));
+        out
+    }
+}
+
+/// Declares the cases of a [`BTLayer::switch`], each one is a sequence.
+pub struct BTSwitch<'a, H: ActionHandler, D: Decorator, C: Composite, K> {
+    layer: BTLayer<'a, H, D, C>,
+    cases: &'a mut Vec<Option<K>>,
+}
+
+impl<H: ActionHandler, D: Decorator, C: Composite + From<StandardComposite>, K>
+    BTSwitch<'_, H, D, C, K>
+{
+    /// Run the sequence built by `layer_fn` when the key is `case`
+    pub fn case<O>(&mut self, case: K, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
+        self.cases.push(Some(case));
+        self.layer.sequence(layer_fn)
+    }
+    pub fn case_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        case: K,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        self.cases.push(Some(case));
+        self.layer.sequence_with_deps(deps, layer_fn)
+    }
+
+    /// Run the sequence built by `layer_fn` when the key doesn't match any of the cases
+    pub fn default<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
+        self.cases.push(None);
+        self.layer.sequence(layer_fn)
+    }
+    pub fn default_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        self.cases.push(None);
+        self.layer.sequence_with_deps(deps, layer_fn)
+    }
+}
+
+impl<H, D, C> BTLayer<'_, H, D, C>
+where
+    H: ActionHandler,
+    D: Decorator + From<StandardDecorator>,
+    C: Composite,
+{
+    /// Graft `bt`, built for another blackboard, into this layer under a
+    /// [`Subtree`](crate::control::decorators::Subtree) node & return the subtree node.
+    ///
+    /// Both the control tree & the dispatch are merged. The leaves, blackboard decorators &
+    /// selectors of `bt` are run against this tree's blackboard through `lens`, so the executors
+    /// & conditionals are mapped into this tree's with `From<Lensed<..>>`. The keys `bt` expects
+    /// to be [preset](BTBuilder::preset) are expected to be preset in this tree too.
+    pub fn mount_subtree<Sub: ActionHandler>(
+        &mut self,
+        lens: Lens<H::Bb, Sub::Bb>,
+        bt: ShrubberyBT<Sub, D, C>,
+    ) -> CTreeNodeID
+    where
+        H::Bb: 'static,
+        Sub::Bb: 'static,
+        H::Execute: From<Lensed<H::Bb, Sub::Bb, Sub::Execute>>,
+        H::Condition: From<Lensed<H::Bb, Sub::Bb, Sub::Condition>>,
+    {
+        let ShrubberyBT {
+            control_tree,
+            dispatch,
+        } = bt;
+        let (subtree, new_ids) = self.control.builder.inner.graft_subtree(
+            self.control.layer_id,
+            usize::MAX,
+            control_tree,
+        );
+        self.dispatch.mount(dispatch, lens, &new_ids);
+        subtree
+    }
+}
+
+/// Declares the options of a [`BTLayer::utility_selector`], each one is a sequence.
+pub struct BTUtility<'a, H: ActionHandler, D: Decorator, C: Composite> {
+    layer: BTLayer<'a, H, D, C>,
+    scorers: &'a mut Vec<fn(&H::Bb) -> f32>,
+}
+
+impl<H: ActionHandler, D: Decorator, C: Composite + From<StandardComposite>>
+    BTUtility<'_, H, D, C>
+{
+    /// Add the sequence built by `layer_fn` as an option, scored by `score`
+    pub fn option<O>(
+        &mut self,
+        score: fn(&H::Bb) -> f32,
+        layer_fn: BTLayerFn<'_, O, H, D, C>,
+    ) -> O {
+        self.scorers.push(score);
+        self.layer.sequence(layer_fn)
+    }
+    pub fn option_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        score: fn(&H::Bb) -> f32,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        self.scorers.push(score);
+        self.layer.sequence_with_deps(deps, layer_fn)
+    }
+}
+
+impl<'a, H: ActionHandler, D: Decorator + From<StandardDecorator>, C: Composite>
+    BTLayer<'a, H, D, C>
+{
+    /// Add a [`Guard`](crate::control::decorators::Guard) node with a [`BlackboardDecorator`]
+    /// attached, and build it's sub-tree
+    pub fn blackboard_decorator<O>(
+        &mut self,
+        decorator: impl BlackboardDecorator<H::Bb> + 'static,
+        layer_fn: BTLayerFn<'_, O, H, D, C>,
+    ) -> O {
+        let guard = D::from(StandardDecorator::guard(decorator.name()));
+        let next_layer = self.control.next_layer(ControlNode::decorator(guard));
+        self.dispatch
+            .add_blackboard_decorator(next_layer.layer_id, decorator);
         layer_fn(BTLayer {
             control: next_layer,
-            dispatch: &mut self.dispatch,
+            dispatch: self.dispatch,
         })
     }
-
-    pub fn control_node_with_deps<Deps, O>(
+    pub fn blackboard_decorator_with_deps<Deps, O>(
         &mut self,
         deps: Deps,
-        node: impl Into<ControlNode<D>>,
-        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D>,
+        decorator: impl BlackboardDecorator<H::Bb> + 'static,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
     ) -> O {
-        let next_layer = self.control.next_layer(node);
+        let guard = D::from(StandardDecorator::guard(decorator.name()));
+        let next_layer = self.control.next_layer(ControlNode::decorator(guard));
+        self.dispatch
+            .add_blackboard_decorator(next_layer.layer_id, decorator);
         layer_fn(
             deps,
             BTLayer {
                 control: next_layer,
-                dispatch: &mut self.dispatch,
+                dispatch: self.dispatch,
             },
         )
     }
-}
 
-impl<'a, H: ActionHandler, D: Decorator + From<StandardDecorator>> BTLayer<'a, H, D> {
-    pub fn repeater<O>(&mut self, retries: usize, layer_fn: BTLayerFn<'_, O, H, D>) -> O {
+    /// Only tick the sub-tree while `condition` succeeds, see [`Precondition`]
+    pub fn precondition<Cond, O>(
+        &mut self,
+        condition: Cond,
+        layer_fn: BTLayerFn<'_, O, H, D, C>,
+    ) -> O
+    where
+        Cond: Conditional<H::Bb> + 'static,
+    {
+        self.blackboard_decorator(Precondition::new(condition), layer_fn)
+    }
+    pub fn precondition_with_deps<Cond, Deps, O>(
+        &mut self,
+        deps: Deps,
+        condition: Cond,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O
+    where
+        Cond: Conditional<H::Bb> + 'static,
+    {
+        self.blackboard_decorator_with_deps(deps, Precondition::new(condition), layer_fn)
+    }
+
+    /// Undo the writes of a sequence to the blackboard if it fails, see [`Transaction`]
+    pub fn transaction<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O
+    where
+        H::Bb: 'static,
+        C: From<StandardComposite>,
+    {
+        self.blackboard_decorator_with_deps(layer_fn, Transaction::new(), |layer_fn, mut tx| {
+            tx.sequence(layer_fn)
+        })
+    }
+    pub fn transaction_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O
+    where
+        H::Bb: 'static,
+        C: From<StandardComposite>,
+    {
+        self.blackboard_decorator_with_deps(
+            (deps, layer_fn),
+            Transaction::new(),
+            |(deps, layer_fn), mut tx| tx.sequence_with_deps(deps, layer_fn),
+        )
+    }
+
+    /// [`Self::transaction`] without a snapshot of the blackboard, see [`UndoTransaction`]
+    pub fn undo_transaction<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O
+    where
+        H::Bb: UndoLog + 'static,
+        C: From<StandardComposite>,
+    {
+        self.blackboard_decorator_with_deps(layer_fn, UndoTransaction::new(), |layer_fn, mut tx| {
+            tx.sequence(layer_fn)
+        })
+    }
+    pub fn undo_transaction_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O
+    where
+        H::Bb: UndoLog + 'static,
+        C: From<StandardComposite>,
+    {
+        self.blackboard_decorator_with_deps(
+            (deps, layer_fn),
+            UndoTransaction::new(),
+            |(deps, layer_fn), mut tx| tx.sequence_with_deps(deps, layer_fn),
+        )
+    }
+
+    pub fn repeater<O>(&mut self, retries: usize, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
         let decorator = D::from(StandardDecorator::repeater(retries));
         let node = ControlNode::decorator(decorator);
         self.control_node(node, layer_fn)
@@ -215,14 +739,14 @@ impl<'a, H: ActionHandler, D: Decorator + From<StandardDecorator>> BTLayer<'a, H
         &mut self,
         deps: Deps,
         retries: usize,
-        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D>,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
     ) -> O {
         let decorator = D::from(StandardDecorator::repeater(retries));
         let node = ControlNode::decorator(decorator);
         self.control_node_with_deps(deps, node, layer_fn)
     }
 
-    pub fn inverter<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D>) -> O {
+    pub fn inverter<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
         let decorator = D::from(StandardDecorator::inverter());
         let node = ControlNode::decorator(decorator);
         self.control_node(node, layer_fn)
@@ -231,14 +755,14 @@ impl<'a, H: ActionHandler, D: Decorator + From<StandardDecorator>> BTLayer<'a, H
     pub fn inverter_with_deps<Deps, O>(
         &mut self,
         deps: Deps,
-        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D>,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
     ) -> O {
         let decorator = D::from(StandardDecorator::inverter());
         let node = ControlNode::decorator(decorator);
         self.control_node_with_deps(deps, node, layer_fn)
     }
 
-    pub fn subtree<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D>) -> O {
+    pub fn subtree<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
         let decorator = D::from(StandardDecorator::subtree());
         let node = ControlNode::decorator(decorator);
         self.control_node(node, layer_fn)
@@ -246,10 +770,193 @@ impl<'a, H: ActionHandler, D: Decorator + From<StandardDecorator>> BTLayer<'a, H
     pub fn subtree_with_deps<Deps, O>(
         &mut self,
         deps: Deps,
-        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D>,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
     ) -> O {
         let decorator = D::from(StandardDecorator::subtree());
         let node = ControlNode::decorator(decorator);
         self.control_node_with_deps(deps, node, layer_fn)
     }
+
+    pub fn timeout<O>(
+        &mut self,
+        duration: Duration,
+        clock: impl Into<ClockHandle>,
+        layer_fn: BTLayerFn<'_, O, H, D, C>,
+    ) -> O {
+        let decorator = D::from(StandardDecorator::timeout(duration, clock));
+        let node = ControlNode::decorator(decorator);
+        self.control_node(node, layer_fn)
+    }
+    pub fn timeout_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        duration: Duration,
+        clock: impl Into<ClockHandle>,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        let decorator = D::from(StandardDecorator::timeout(duration, clock));
+        let node = ControlNode::decorator(decorator);
+        self.control_node_with_deps(deps, node, layer_fn)
+    }
+
+    /// See [`RetryWithBackoff`](crate::control::decorators::RetryWithBackoff), use
+    /// [`Self::decorator`] to add jitter
+    pub fn retry_with_backoff<O>(
+        &mut self,
+        retries: usize,
+        backoff: Backoff,
+        clock: impl Into<ClockHandle>,
+        layer_fn: BTLayerFn<'_, O, H, D, C>,
+    ) -> O {
+        let decorator = D::from(StandardDecorator::retry_with_backoff(
+            retries, backoff, clock,
+        ));
+        let node = ControlNode::decorator(decorator);
+        self.control_node(node, layer_fn)
+    }
+    pub fn retry_with_backoff_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        retries: usize,
+        backoff: Backoff,
+        clock: impl Into<ClockHandle>,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        let decorator = D::from(StandardDecorator::retry_with_backoff(
+            retries, backoff, clock,
+        ));
+        let node = ControlNode::decorator(decorator);
+        self.control_node_with_deps(deps, node, layer_fn)
+    }
+
+    pub fn cooldown<O>(
+        &mut self,
+        cooldown: Duration,
+        clock: impl Into<ClockHandle>,
+        layer_fn: BTLayerFn<'_, O, H, D, C>,
+    ) -> O {
+        let decorator = D::from(StandardDecorator::cooldown(cooldown, clock));
+        let node = ControlNode::decorator(decorator);
+        self.control_node(node, layer_fn)
+    }
+    pub fn cooldown_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        cooldown: Duration,
+        clock: impl Into<ClockHandle>,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        let decorator = D::from(StandardDecorator::cooldown(cooldown, clock));
+        let node = ControlNode::decorator(decorator);
+        self.control_node_with_deps(deps, node, layer_fn)
+    }
+
+    pub fn repeat_n<O>(&mut self, n: usize, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
+        let decorator = D::from(StandardDecorator::repeat_n(n));
+        let node = ControlNode::decorator(decorator);
+        self.control_node(node, layer_fn)
+    }
+    pub fn repeat_n_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        n: usize,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        let decorator = D::from(StandardDecorator::repeat_n(n));
+        let node = ControlNode::decorator(decorator);
+        self.control_node_with_deps(deps, node, layer_fn)
+    }
+
+    pub fn retry_until_successful<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
+        let decorator = D::from(StandardDecorator::retry_until_successful());
+        let node = ControlNode::decorator(decorator);
+        self.control_node(node, layer_fn)
+    }
+    pub fn retry_until_successful_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        let decorator = D::from(StandardDecorator::retry_until_successful());
+        let node = ControlNode::decorator(decorator);
+        self.control_node_with_deps(deps, node, layer_fn)
+    }
+
+    pub fn keep_running_until_failure<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
+        let decorator = D::from(StandardDecorator::keep_running_until_failure());
+        let node = ControlNode::decorator(decorator);
+        self.control_node(node, layer_fn)
+    }
+    pub fn keep_running_until_failure_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        let decorator = D::from(StandardDecorator::keep_running_until_failure());
+        let node = ControlNode::decorator(decorator);
+        self.control_node_with_deps(deps, node, layer_fn)
+    }
+
+    /// Never finishes on its own, see [`Infinite`](crate::control::decorators::Infinite)
+    pub fn infinite<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
+        let decorator = D::from(StandardDecorator::infinite());
+        let node = ControlNode::decorator(decorator);
+        self.control_node(node, layer_fn)
+    }
+    pub fn infinite_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        let decorator = D::from(StandardDecorator::infinite());
+        let node = ControlNode::decorator(decorator);
+        self.control_node_with_deps(deps, node, layer_fn)
+    }
+
+    pub fn force_success<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
+        let decorator = D::from(StandardDecorator::force_success());
+        let node = ControlNode::decorator(decorator);
+        self.control_node(node, layer_fn)
+    }
+    pub fn force_success_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        let decorator = D::from(StandardDecorator::force_success());
+        let node = ControlNode::decorator(decorator);
+        self.control_node_with_deps(deps, node, layer_fn)
+    }
+
+    pub fn force_failure<O>(&mut self, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
+        let decorator = D::from(StandardDecorator::force_failure());
+        let node = ControlNode::decorator(decorator);
+        self.control_node(node, layer_fn)
+    }
+    pub fn force_failure_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        let decorator = D::from(StandardDecorator::force_failure());
+        let node = ControlNode::decorator(decorator);
+        self.control_node_with_deps(deps, node, layer_fn)
+    }
+
+    /// See [`RunOnce`] for returning a fixed status or surviving resets
+    pub fn run_once<O>(&mut self, run_once: RunOnce, layer_fn: BTLayerFn<'_, O, H, D, C>) -> O {
+        let decorator = D::from(StandardDecorator::from(run_once));
+        let node = ControlNode::decorator(decorator);
+        self.control_node(node, layer_fn)
+    }
+    pub fn run_once_with_deps<Deps, O>(
+        &mut self,
+        deps: Deps,
+        run_once: RunOnce,
+        layer_fn: BTLayerFnWithDeps<'_, Deps, O, H, D, C>,
+    ) -> O {
+        let decorator = D::from(StandardDecorator::from(run_once));
+        let node = ControlNode::decorator(decorator);
+        self.control_node_with_deps(deps, node, layer_fn)
+    }
 }
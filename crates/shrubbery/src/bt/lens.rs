@@ -0,0 +1,370 @@
+//! # Blackboard lenses, for mounting a [`ShrubberyBT`](super::ShrubberyBT) inside another one
+//!
+//! A subtree written against its own blackboard type can be grafted into a tree with a bigger
+//! blackboard, as long as the subtree's blackboard can be borrowed from it. The [`Lens`] does the
+//! borrowing & every leaf of the subtree is wrapped in a [`Lensed`] that applies it, see
+//! [`BTLayer::mount_subtree`](super::builder::BTLayer::mount_subtree).
+
+use crate::kv_blackboard::Ports;
+use crate::traits::*;
+use crate::Status;
+
+/// Borrows the `SubBB` part of a `BB`.
+#[derive(Debug)]
+pub struct Lens<BB, SubBB> {
+    /// For [`Conditional`]s & [`BlackboardDecorator`]s, which only read the blackboard
+    pub view: fn(&BB) -> &SubBB,
+    pub view_mut: fn(&mut BB) -> &mut SubBB,
+}
+
+impl<BB, SubBB> Lens<BB, SubBB> {
+    pub fn new(view: fn(&BB) -> &SubBB, view_mut: fn(&mut BB) -> &mut SubBB) -> Self {
+        Self { view, view_mut }
+    }
+}
+
+// derives would needlessly require the blackboards to be `Copy`
+
+impl<BB, SubBB> Clone for Lens<BB, SubBB> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<BB, SubBB> Copy for Lens<BB, SubBB> {}
+
+/// An [`Executor`], [`Conditional`], [`BlackboardDecorator`] or [`BlackboardSelector`] written for
+/// `SubBB`, run against a `BB` through a [`Lens`].
+///
+/// Add a variant holding it to your executor & conditional types to mount subtrees, e.g.
+/// `Sub(Lensed<BB, SubBB, SubExecutors>)`.
+#[derive(Debug, Clone)]
+pub struct Lensed<BB, SubBB, T> {
+    pub lens: Lens<BB, SubBB>,
+    pub inner: T,
+}
+
+impl<BB, SubBB, T> Lensed<BB, SubBB, T> {
+    pub fn new(lens: Lens<BB, SubBB>, inner: T) -> Self {
+        Self { lens, inner }
+    }
+}
+
+/// The ports are those of the inner executor, so the keys a subtree writes can be read by the rest
+/// of the tree. They name keys of `SubBB`, so a lens into a
+/// [`KvBlackboard`](crate::kv_blackboard::KvBlackboard) nested in the tree's own shares its key
+/// names with the tree.
+impl<BB: Blackboard, SubBB: Blackboard, E: Executor<SubBB>> Executor<BB> for Lensed<BB, SubBB, E> {
+    fn execute(&self, blackboard: &mut BB) -> Status {
+        self.inner.execute((self.lens.view_mut)(blackboard))
+    }
+    fn halt(&self, blackboard: &mut BB) {
+        self.inner.halt((self.lens.view_mut)(blackboard))
+    }
+    fn on_start(&mut self, blackboard: &mut BB) -> Status {
+        self.inner.on_start((self.lens.view_mut)(blackboard))
+    }
+    fn on_running(&mut self, blackboard: &mut BB) -> Status {
+        self.inner.on_running((self.lens.view_mut)(blackboard))
+    }
+    fn on_end(&mut self, blackboard: &mut BB, status: Status) {
+        self.inner.on_end((self.lens.view_mut)(blackboard), status)
+    }
+    fn on_halted(&mut self, blackboard: &mut BB) {
+        self.inner.on_halted((self.lens.view_mut)(blackboard))
+    }
+    fn name(&self) -> Option<String> {
+        self.inner.name()
+    }
+    fn details(&self) -> Option<String> {
+        self.inner.details()
+    }
+    fn ports(&self) -> Ports {
+        self.inner.ports()
+    }
+}
+
+impl<BB: Blackboard, SubBB: Blackboard, C: Conditional<SubBB>> Conditional<BB>
+    for Lensed<BB, SubBB, C>
+{
+    fn conditional(&self, blackboard: &BB) -> Status {
+        self.inner.conditional((self.lens.view)(blackboard))
+    }
+    fn name(&self) -> Option<String> {
+        self.inner.name()
+    }
+    fn details(&self) -> Option<String> {
+        self.inner.details()
+    }
+    fn ports(&self) -> Ports {
+        self.inner.ports()
+    }
+}
+
+impl<BB: Blackboard + 'static, SubBB: Blackboard + 'static> BlackboardDecorator<BB>
+    for Lensed<BB, SubBB, Box<dyn BlackboardDecorator<SubBB>>>
+{
+    fn pre_tick(&mut self, blackboard: &BB) -> PreTick {
+        self.inner.pre_tick((self.lens.view)(blackboard))
+    }
+    fn before_tick(&mut self, blackboard: &mut BB) {
+        self.inner.before_tick((self.lens.view_mut)(blackboard))
+    }
+    fn post_tick(&mut self, blackboard: &mut BB, status: Status) {
+        self.inner
+            .post_tick((self.lens.view_mut)(blackboard), status)
+    }
+    fn halt(&mut self, blackboard: &mut BB) {
+        self.inner.halt((self.lens.view_mut)(blackboard))
+    }
+    fn name(&self) -> Option<String> {
+        self.inner.name()
+    }
+}
+
+impl<BB: Blackboard + 'static, SubBB: Blackboard + 'static> BlackboardSelector<BB>
+    for Lensed<BB, SubBB, Box<dyn BlackboardSelector<SubBB>>>
+{
+    fn select(&mut self, blackboard: &BB, children: usize) -> Selection {
+        self.inner.select((self.lens.view)(blackboard), children)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use derive_more::From;
+
+    use super::*;
+    use crate::bt::ShrubberyBT;
+    use crate::control::ROOT_ID;
+    use crate::kv_blackboard::{Key, KvBlackboard};
+
+    #[derive(Debug, Default, Clone)]
+    struct Robot {
+        ticks: u32,
+        arm: Arm,
+    }
+
+    #[derive(Debug, Default, Clone)]
+    struct Arm {
+        moves: Vec<&'static str>,
+    }
+
+    /// Arm subtree leaf
+    #[derive(Debug, Clone)]
+    struct Move(&'static str);
+
+    impl Executor<Arm> for Move {
+        fn execute(&self, arm: &mut Arm) -> Status {
+            arm.moves.push(self.0);
+            Status::Success
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct MovedAtLeast(usize);
+
+    impl Conditional<Arm> for MovedAtLeast {
+        fn conditional(&self, arm: &Arm) -> Status {
+            (arm.moves.len() >= self.0).into()
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct ArmHandler;
+
+    impl ActionHandler for ArmHandler {
+        type Bb = Arm;
+        type Execute = Move;
+        type Condition = MovedAtLeast;
+    }
+
+    #[derive(Debug, Clone)]
+    struct Tick;
+
+    #[derive(Debug, Clone, From)]
+    enum Tasks {
+        Tick(Tick),
+        Arm(Lensed<Robot, Arm, Move>),
+    }
+
+    impl Executor<Robot> for Tasks {
+        fn execute(&self, robot: &mut Robot) -> Status {
+            match self {
+                Tasks::Tick(_) => {
+                    robot.ticks += 1;
+                    Status::Success
+                }
+                Tasks::Arm(m) => m.execute(robot),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, From)]
+    enum Checks {
+        Arm(Lensed<Robot, Arm, MovedAtLeast>),
+    }
+
+    impl Conditional<Robot> for Checks {
+        fn conditional(&self, robot: &Robot) -> Status {
+            match self {
+                Checks::Arm(c) => c.conditional(robot),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct RobotHandler;
+
+    impl ActionHandler for RobotHandler {
+        type Bb = Robot;
+        type Execute = Tasks;
+        type Condition = Checks;
+    }
+
+    fn arm_lens() -> Lens<Robot, Arm> {
+        Lens::new(|robot| &robot.arm, |robot| &mut robot.arm)
+    }
+
+    #[test]
+    fn mount_subtree() {
+        let mut arm = ShrubberyBT::<ArmHandler>::builder();
+        arm.layer(|mut root| {
+            root.execute(Move("reach"));
+            root.condition(MovedAtLeast(1));
+            // blackboard decorators come along too
+            root.precondition(MovedAtLeast(1), |mut guarded| {
+                guarded.execute(Move("grab"));
+            });
+        });
+        let arm = arm.build().unwrap();
+
+        let mut builder = ShrubberyBT::<RobotHandler>::builder();
+        builder.layer_with_deps(arm, |arm, mut root| {
+            root.execute(Tick);
+            root.mount_subtree(arm_lens(), arm);
+            root.execute(Tick);
+        });
+        let mut bt = builder.build().unwrap();
+        let mut robot = Robot::default();
+
+        assert_eq!(bt.tick(&mut robot), Status::Success);
+        assert_eq!(robot.ticks, 2);
+        assert_eq!(robot.arm.moves, vec!["reach", "grab"]);
+
+        // the subtree's nodes were given their new ids
+        let subtree = bt.control_tree.children(&ROOT_ID)[1];
+        assert!(bt.control_tree[subtree].try_as_control().is_some());
+        for (_, children) in bt.control_tree.iter_tree() {
+            for &child in children {
+                assert_eq!(bt.control_tree[child].id(), Some(child));
+            }
+        }
+    }
+
+    const START: Key<u32> = Key::new("start");
+    const END: Key<u32> = Key::new("end");
+
+    /// Sets a key, declaring it as an output
+    #[derive(Debug, Clone)]
+    struct Set(Key<u32>);
+
+    impl Executor<KvBlackboard> for Set {
+        fn execute(&self, blackboard: &mut KvBlackboard) -> Status {
+            blackboard.set(self.0, 1);
+            Status::Success
+        }
+        fn ports(&self) -> Ports {
+            Ports::new().output(self.0)
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct IsSet(Key<u32>);
+
+    impl Conditional<KvBlackboard> for IsSet {
+        fn conditional(&self, blackboard: &KvBlackboard) -> Status {
+            blackboard.get(self.0).is_some().into()
+        }
+        fn ports(&self) -> Ports {
+            Ports::new().input(self.0)
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct KvHandler;
+
+    impl ActionHandler for KvHandler {
+        type Bb = KvBlackboard;
+        type Execute = Set;
+        type Condition = IsSet;
+    }
+
+    #[derive(Debug, Clone, From)]
+    enum Mounted<T> {
+        Own(T),
+        Sub(Lensed<KvBlackboard, KvBlackboard, T>),
+    }
+
+    impl Executor<KvBlackboard> for Mounted<Set> {
+        fn execute(&self, blackboard: &mut KvBlackboard) -> Status {
+            match self {
+                Mounted::Own(set) => set.execute(blackboard),
+                Mounted::Sub(set) => set.execute(blackboard),
+            }
+        }
+        fn ports(&self) -> Ports {
+            match self {
+                Mounted::Own(set) => set.ports(),
+                Mounted::Sub(set) => set.ports(),
+            }
+        }
+    }
+
+    impl Conditional<KvBlackboard> for Mounted<IsSet> {
+        fn conditional(&self, blackboard: &KvBlackboard) -> Status {
+            match self {
+                Mounted::Own(check) => check.conditional(blackboard),
+                Mounted::Sub(check) => check.conditional(blackboard),
+            }
+        }
+        fn ports(&self) -> Ports {
+            match self {
+                Mounted::Own(check) => check.ports(),
+                Mounted::Sub(check) => check.ports(),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct MountingHandler;
+
+    impl ActionHandler for MountingHandler {
+        type Bb = KvBlackboard;
+        type Execute = Mounted<Set>;
+        type Condition = Mounted<IsSet>;
+    }
+
+    #[test]
+    fn mount_subtree_ports() {
+        let mut sub = ShrubberyBT::<KvHandler>::builder();
+        sub.layer(|mut root| {
+            root.condition(IsSet(START));
+            root.execute(Set(END));
+        });
+        sub.preset(START);
+        let sub = sub.build().unwrap();
+
+        // the subtree's preset carries over, & the key it writes can be read after it
+        let mut builder = ShrubberyBT::<MountingHandler>::builder();
+        builder.layer_with_deps(sub, |sub, mut root| {
+            root.mount_subtree(Lens::new(|bb| bb, |bb| bb), sub);
+            root.condition(Mounted::Own(IsSet(END)));
+        });
+        let mut bt = builder.build().unwrap();
+
+        let mut blackboard = KvBlackboard::new().with(START, 1);
+        assert_eq!(bt.tick(&mut blackboard), Status::Success);
+        assert_eq!(blackboard.get(END), Some(&1));
+    }
+}
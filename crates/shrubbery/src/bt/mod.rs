@@ -1,24 +1,30 @@
 //! # Full BT
 
-use crate::control::ControlTree;
+use crate::control::{CTreeNodeID, ControlTree, ROOT_ID};
 use crate::executor_mask::{LeafDispatch, TaskHook};
 use crate::graphviz::GraphvizAttrs;
-use crate::prelude::{BTBuilder, StandardDecorator};
+use crate::prelude::{BTBuilder, StandardComposite, StandardDecorator};
 use crate::traits::*;
 use crate::Status;
 
+pub mod blackboard_decorators;
 pub mod builder;
+pub mod lens;
 
 /* 4x generics Bt */
 
 /// Behavior Tree with [`Executor`] and [`Conditional`] dispatch
 #[derive(Debug, Clone)]
-pub struct ShrubberyBT<Handler: ActionHandler, Decor: Decorator = StandardDecorator> {
-    pub(crate) control_tree: ControlTree<Decor>,
+pub struct ShrubberyBT<
+    Handler: ActionHandler,
+    Decor: Decorator = StandardDecorator,
+    Comp: Composite = StandardComposite,
+> {
+    pub(crate) control_tree: ControlTree<Decor, Comp>,
     pub(crate) dispatch: LeafDispatch<Handler>,
 }
 
-impl<H: ActionHandler, D: Decorator> Default for ShrubberyBT<H, D> {
+impl<H: ActionHandler, D: Decorator, C: Composite> Default for ShrubberyBT<H, D, C> {
     fn default() -> Self {
         Self {
             control_tree: Default::default(),
@@ -27,42 +33,78 @@ impl<H: ActionHandler, D: Decorator> Default for ShrubberyBT<H, D> {
     }
 }
 
-impl<H: ActionHandler, D: Decorator> ShrubberyBT<H, D> {
+impl<H: ActionHandler, D: Decorator, C: Composite> ShrubberyBT<H, D, C> {
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn builder() -> BTBuilder<H, D> {
+    pub fn builder() -> BTBuilder<H, D, C> {
         BTBuilder::new()
     }
 
-    pub fn into_builder(self) -> BTBuilder<H, D> {
+    pub fn into_builder(self) -> BTBuilder<H, D, C> {
         BTBuilder::from(self)
     }
 
+    /// Advance the behavior tree by a single traversal, see [`ControlTree::tick`].
+    pub fn tick(&mut self, blackboard: &mut H::Bb) -> Status {
+        let mut task_hook = TaskHook::new(&mut self.dispatch, blackboard);
+        self.control_tree.tick(&mut task_hook)
+    }
+
+    /// [`Self::tick`], calling `cb` as the control tree is traversed, see
+    /// [`ControlTree::tick_with_update_callback`].
+    pub fn tick_with_update_callback(
+        &mut self,
+        blackboard: &mut H::Bb,
+        cb: &mut impl UpdateCallback<D, C>,
+    ) -> Status {
+        let mut task_hook = TaskHook::new(&mut self.dispatch, blackboard);
+        self.control_tree
+            .tick_with_update_callback(&mut task_hook, cb)
+    }
+
+    /// The status of the whole behavior tree.
+    pub fn status(&self) -> Status {
+        self.control_tree.status()
+    }
+
+    /// Run the behavior tree to completion, see [`ControlTree::run`].
+    ///
+    /// The tree is kept in place, so calling this again restarts it from the beginning.
     pub fn run(&mut self, blackboard: &mut H::Bb) -> Status {
-        let mut control_tree = std::mem::take(&mut self.control_tree);
-        let dispatch = &self.dispatch;
+        let mut task_hook = TaskHook::new(&mut self.dispatch, blackboard);
+        self.control_tree.run(&mut task_hook)
+    }
 
-        let mut task_hook = TaskHook {
-            dispatch,
-            blackboard,
-        };
-        control_tree.run(&mut task_hook)
+    /// Reset every node in the tree, see [`ControlTree::reset`]. Executors that are still running
+    /// are halted first, and so is every [`BlackboardDecorator`] (e.g. dropping the snapshot of a
+    /// [`Transaction`](blackboard_decorators::Transaction)).
+    pub fn reset(&mut self, blackboard: &mut H::Bb) {
+        let mut task_hook = TaskHook::new(&mut self.dispatch, blackboard);
+        self.control_tree.halt(ROOT_ID, &mut task_hook);
+        self.dispatch.halt_decorators(blackboard);
+        self.control_tree.reset();
+    }
+
+    /// Cancel the branch under `node_id`, calling [`Executor::halt`] on every running executor
+    /// in it, see [`ControlTree::halt`].
+    pub fn halt(&mut self, node_id: CTreeNodeID, blackboard: &mut H::Bb) {
+        let mut task_hook = TaskHook::new(&mut self.dispatch, blackboard);
+        self.control_tree.halt(node_id, &mut task_hook);
     }
 }
 
-impl<H: ActionHandler, D: Decorator + GraphvizAttrs> ShrubberyBT<H, D> {
+impl<H: ActionHandler, D: Decorator + GraphvizAttrs, C: Composite + GraphvizAttrs>
+    ShrubberyBT<H, D, C>
+{
     pub fn run_save_animation(
         &mut self,
         blackboard: &mut H::Bb,
         file_name: &str,
         frame_time: f32,
     ) -> Status {
-        let mut task_hook = TaskHook {
-            dispatch: &self.dispatch,
-            blackboard,
-        };
+        let mut task_hook = TaskHook::new(&mut self.dispatch, blackboard);
         self.control_tree
             .run_save_animation(&mut task_hook, file_name, frame_time)
     }
@@ -71,3 +113,309 @@ impl<H: ActionHandler, D: Decorator + GraphvizAttrs> ShrubberyBT<H, D> {
         self.control_tree.save_dot(name);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Debug, Default, Clone)]
+    struct TraceHandler;
+
+    impl ActionHandler for TraceHandler {
+        type Bb = Vec<&'static str>;
+        type Execute = Trace;
+        type Condition = Trace;
+    }
+
+    /// Pushes its name onto the blackboard and returns a fixed status.
+    #[derive(Debug, Clone)]
+    struct Trace(&'static str, Status);
+
+    impl Executor<Vec<&'static str>> for Trace {
+        fn execute(&self, blackboard: &mut Vec<&'static str>) -> Status {
+            blackboard.push(self.0);
+            self.1
+        }
+        fn halt(&self, blackboard: &mut Vec<&'static str>) {
+            blackboard.push("halt");
+        }
+    }
+
+    impl Conditional<Vec<&'static str>> for Trace {
+        fn conditional(&self, _: &Vec<&'static str>) -> Status {
+            self.1
+        }
+    }
+
+    fn trace_bt() -> ShrubberyBT<TraceHandler> {
+        let mut builder = ShrubberyBT::<TraceHandler>::builder();
+        builder.layer(|mut root| {
+            root.repeater(2, |mut repeat| {
+                repeat.sequence(|mut seq| {
+                    seq.execute(Trace("a", Status::Success));
+                    seq.execute(Trace("b", Status::Failure));
+                });
+            });
+        });
+        builder.build().unwrap()
+    }
+
+    #[derive(Debug, Default, Clone)]
+    struct CountdownHandler;
+
+    impl ActionHandler for CountdownHandler {
+        type Bb = Vec<&'static str>;
+        type Execute = Countdown;
+        type Condition = Trace;
+    }
+
+    /// Stays running for `ticks` ticks, keeping count itself & logging its lifecycle.
+    #[derive(Debug, Clone)]
+    struct Countdown {
+        ticks: usize,
+        left: usize,
+    }
+
+    impl Executor<Vec<&'static str>> for Countdown {
+        fn execute(&self, blackboard: &mut Vec<&'static str>) -> Status {
+            self.clone().on_start(blackboard)
+        }
+        fn on_start(&mut self, blackboard: &mut Vec<&'static str>) -> Status {
+            blackboard.push("start");
+            self.left = self.ticks;
+            self.on_running(blackboard)
+        }
+        fn on_running(&mut self, blackboard: &mut Vec<&'static str>) -> Status {
+            blackboard.push("running");
+            if self.left == 0 {
+                Status::Success
+            } else {
+                self.left -= 1;
+                Status::Running
+            }
+        }
+        fn on_end(&mut self, blackboard: &mut Vec<&'static str>, _: Status) {
+            blackboard.push("end");
+        }
+        fn on_halted(&mut self, blackboard: &mut Vec<&'static str>) {
+            blackboard.push("halted");
+        }
+    }
+
+    fn countdown_bt(ticks: usize) -> (ShrubberyBT<CountdownHandler>, CTreeNodeID) {
+        let mut builder = ShrubberyBT::<CountdownHandler>::builder();
+        let leaf = builder.layer_with_deps(ticks, |ticks, mut root| {
+            root.execute(Countdown { ticks, left: 0 })
+        });
+        (builder.build().unwrap(), leaf)
+    }
+
+    #[test]
+    fn stateful_executor() {
+        let (mut bt, leaf) = countdown_bt(2);
+        let expected = ["start", "running", "running", "running", "end"];
+
+        for _ in 0..2 {
+            let mut trace = vec![];
+            assert_eq!(bt.tick(&mut trace), Status::Running);
+            assert_eq!(bt.dispatch.executor(&leaf).unwrap().left, 1);
+            assert_eq!(bt.run(&mut trace), Status::Success);
+            assert_eq!(trace, expected);
+        }
+    }
+
+    #[test]
+    fn stateful_executor_halted() {
+        let (mut bt, leaf) = countdown_bt(5);
+        let mut trace = vec![];
+        assert_eq!(bt.tick(&mut trace), Status::Running);
+        bt.halt(leaf, &mut trace);
+        assert_eq!(trace, ["start", "running", "halted"]);
+
+        // starts from scratch after being halted
+        trace.clear();
+        assert_eq!(bt.tick(&mut trace), Status::Running);
+        assert_eq!(trace, ["start", "running"]);
+    }
+
+    #[test]
+    fn repeated_runs() {
+        let mut bt = trace_bt();
+        let expected = ["a", "b", "a", "b", "a", "b"];
+
+        for _ in 0..3 {
+            let mut trace = vec![];
+            assert_eq!(bt.run(&mut trace), Status::Failure);
+            assert_eq!(trace, expected);
+        }
+    }
+
+    #[test]
+    fn reset() {
+        let mut bt = trace_bt();
+        let mut trace = vec![];
+
+        // first attempt fails, the repeater wants to go again
+        assert_eq!(bt.tick(&mut trace), Status::Running);
+        assert_eq!(trace, ["a", "b"]);
+
+        bt.reset(&mut trace);
+        assert!(bt
+            .control_tree
+            .nodes
+            .iter()
+            .all(|node| node.status().is_none()));
+
+        // the repeater has all its retries again
+        trace.clear();
+        assert_eq!(bt.run(&mut trace), Status::Failure);
+        assert_eq!(trace, ["a", "b", "a", "b", "a", "b"]);
+    }
+
+    #[test]
+    fn reset_halts_running() {
+        let (mut bt, _) = countdown_bt(5);
+        let mut trace = vec![];
+        assert_eq!(bt.tick(&mut trace), Status::Running);
+
+        bt.reset(&mut trace);
+        assert_eq!(trace, ["start", "running", "halted"]);
+        assert!(bt.control_tree[ROOT_ID].status().is_none());
+    }
+
+    #[test]
+    fn halt_preempted_executor() {
+        let mut builder = ShrubberyBT::<TraceHandler>::builder();
+        builder.layer(|mut root| {
+            root.fallback(|mut fallback| {
+                fallback.execute(Trace("slow", Status::Running));
+                fallback.execute(Trace("fast", Status::Success));
+            });
+        });
+        let mut bt = builder.build().unwrap();
+
+        let mut trace = vec![];
+        assert_eq!(bt.tick(&mut trace), Status::Success);
+        assert_eq!(trace, ["slow", "fast", "halt"]);
+    }
+
+    #[test]
+    fn halt_from_application() {
+        let mut builder = ShrubberyBT::<TraceHandler>::builder();
+        let seq = builder.layer(|mut root| {
+            root.sequence(|mut seq| {
+                seq.execute(Trace("slow", Status::Running));
+                seq.layer_id()
+            })
+        });
+        let mut bt = builder.build().unwrap();
+
+        let mut trace = vec![];
+        assert_eq!(bt.tick(&mut trace), Status::Running);
+        bt.halt(seq, &mut trace);
+        assert_eq!(trace, ["slow", "halt"]);
+        assert!(bt.control_tree[seq].status().is_none());
+
+        // halting something that isn't running does nothing
+        bt.halt(seq, &mut trace);
+        assert_eq!(trace, ["slow", "halt"]);
+    }
+
+    /// Counts how many times it's been checked, despite only being read
+    #[derive(Debug, Default, Clone, PartialEq)]
+    struct Sneaky {
+        checked: std::cell::Cell<u32>,
+    }
+
+    #[derive(Debug, Clone)]
+    struct CountChecks;
+
+    impl Conditional<Sneaky> for CountChecks {
+        fn conditional(&self, sneaky: &Sneaky) -> Status {
+            sneaky.checked.set(sneaky.checked.get() + 1);
+            Status::Success
+        }
+    }
+
+    impl Executor<Sneaky> for CountChecks {
+        fn execute(&self, _: &mut Sneaky) -> Status {
+            Status::Success
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct SneakyHandler;
+
+    impl ActionHandler for SneakyHandler {
+        type Bb = Sneaky;
+        type Execute = CountChecks;
+        type Condition = CountChecks;
+    }
+
+    fn sneaky_bt(check_read_only: bool) -> ShrubberyBT<SneakyHandler> {
+        let mut builder = ShrubberyBT::<SneakyHandler>::builder();
+        builder.layer(|mut root| {
+            root.execute(CountChecks);
+            root.condition(CountChecks);
+        });
+        if check_read_only {
+            builder.check_read_only();
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn read_only_unchecked() {
+        let mut sneaky = Sneaky::default();
+        assert_eq!(sneaky_bt(false).tick(&mut sneaky), Status::Success);
+        assert_eq!(sneaky.checked.get(), 1);
+    }
+
+    /// Keeps every message logged by the tests
+    struct Logged(Mutex<Vec<String>>);
+
+    impl log::Log for Logged {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+        fn log(&self, record: &log::Record) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+        fn flush(&self) {}
+    }
+
+    static LOGGED: Logged = Logged(Mutex::new(vec![]));
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn read_only_checked() {
+        let _ = log::set_logger(&LOGGED);
+        log::set_max_level(log::LevelFilter::Warn);
+
+        let mut bt = sneaky_bt(true);
+        assert_eq!(bt.tick(&mut Sneaky::default()), Status::Success);
+
+        let mut builder = ShrubberyBT::<SneakyHandler>::builder();
+        builder.layer(|mut root| {
+            root.precondition(CountChecks, |mut guarded| {
+                guarded.execute(CountChecks);
+            });
+        });
+        builder.check_read_only();
+        let mut bt = builder.build().unwrap();
+        assert_eq!(bt.tick(&mut Sneaky::default()), Status::Success);
+
+        // reported, not panicked
+        let logged = LOGGED.0.lock().unwrap();
+        let reported = |name: &str| {
+            logged.iter().any(|message| {
+                message.starts_with(name)
+                    && message.ends_with("changed the blackboard, it must be read-only")
+            })
+        };
+        assert!(reported("Conditional CountChecks"));
+        assert!(reported("Blackboard decorator Precondition"));
+    }
+}
@@ -0,0 +1,104 @@
+//! # Time sources for time based nodes
+//!
+//! Nodes like [`Timeout`](crate::control::decorators::Timeout) never read the system time
+//! directly, they ask a [`Clock`] held through a [`ClockHandle`]. Use [`SystemClock`] for real
+//! time, or [`ManualClock`] to step time by hand so tests stay deterministic without sleeping.
+
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Monotonic time source.
+pub trait Clock: Debug + Send + Sync {
+    /// Time elapsed since some fixed, clock specific, epoch.
+    fn now(&self) -> Duration;
+}
+
+/// Real time, measured from when the clock was created.
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    epoch: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            epoch: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.epoch.elapsed()
+    }
+}
+
+/// Virtual time that only moves when it's told to. Clones share the same time.
+#[derive(Debug, Default, Clone)]
+pub struct ManualClock(Arc<Mutex<Duration>>);
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move time forward by `by`
+    pub fn advance(&self, by: Duration) {
+        *self.0.lock().unwrap() += by;
+    }
+
+    /// Jump to `now`
+    pub fn set(&self, now: Duration) {
+        *self.0.lock().unwrap() = now;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.0.lock().unwrap()
+    }
+}
+
+/// Shared [`Clock`], so nodes can hold one & still be compared, hashed and cloned. Two handles
+/// are equal if they point to the same clock.
+#[derive(Debug, Clone)]
+pub struct ClockHandle(Arc<dyn Clock>);
+
+impl ClockHandle {
+    pub fn new(clock: impl Clock + 'static) -> Self {
+        Self(Arc::new(clock))
+    }
+
+    /// See [`Clock::now`]
+    pub fn now(&self) -> Duration {
+        self.0.now()
+    }
+}
+
+impl Default for ClockHandle {
+    fn default() -> Self {
+        Self::new(SystemClock::default())
+    }
+}
+
+impl<C: Clock + 'static> From<C> for ClockHandle {
+    fn from(clock: C) -> Self {
+        Self::new(clock)
+    }
+}
+
+impl PartialEq for ClockHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ClockHandle {}
+
+impl Hash for ClockHandle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as *const () as usize).hash(state);
+    }
+}
@@ -0,0 +1,291 @@
+//! # Leaf nodes that run on separate threads
+//!
+//! The children of a [`Parallel`] node are semantically concurrent, but by default they are
+//! still ticked one after another. A parallel node built with [`Parallel::run_concurrently`]
+//! hands its leaf children to [`ExecutorHook::hook_concurrent`] as a single batch instead, and
+//! [`ConcurrentTaskHook`] runs that batch on a [`ThreadPool`].
+//!
+//! Executors can't all hold the `&mut` [`Blackboard`] at the same time, so it's split with
+//! [`SplitBlackboard`]: every executor in the batch gets its own fork to write to, the forks are
+//! merged back once the batch is done. Conditionals only read, so they share the blackboard as it
+//! was before the batch started.
+//!
+//! [`Parallel`]: crate::control::control_nodes::Parallel
+//! [`Parallel::run_concurrently`]: crate::control::control_nodes::Parallel::run_concurrently
+
+use crate::bt::ShrubberyBT;
+use crate::control::{CTreeNodeID, LeafNode};
+use crate::executor_mask::{conditional_name, tick_conditional, tick_executor, TaskHook, TaskMut};
+use crate::traits::*;
+use crate::Status;
+
+/// A unit of work handed to a [`ThreadPool`].
+pub type Job<'env> = Box<dyn FnOnce() + Send + 'env>;
+
+/// Runs a batch of [`Job`]s, which may borrow from the caller, to completion.
+///
+/// Implement this to run concurrent leaves on your own pool, e.g. with `rayon::scope`.
+pub trait ThreadPool {
+    /// Run every job, returning once they have all finished.
+    fn scope<'env>(&self, jobs: Vec<Job<'env>>);
+}
+
+/// [`ThreadPool`] that spawns a scoped std thread per job.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ScopedThreads;
+
+impl ThreadPool for ScopedThreads {
+    fn scope<'env>(&self, jobs: Vec<Job<'env>>) {
+        std::thread::scope(|scope| {
+            for job in jobs {
+                scope.spawn(job);
+            }
+        });
+    }
+}
+
+/// A [`Blackboard`] that can be handed out to several executors at once.
+pub trait SplitBlackboard: Blackboard + Send + Sync {
+    /// Copy of the blackboard for a single executor to write to.
+    fn fork(&self) -> Self {
+        self.clone()
+    }
+
+    /// Fold the forks written by a batch of executors back in, they are in the same order as the
+    /// leaves.
+    fn merge(&mut self, forks: Vec<Self>);
+}
+
+/// [`TaskHook`] that runs the leaves of a concurrent [`Parallel`] node on a [`ThreadPool`].
+///
+/// [`Parallel`]: crate::control::control_nodes::Parallel
+pub struct ConcurrentTaskHook<'a, H: ActionHandler, P: ThreadPool = ScopedThreads> {
+    pub tasks: TaskHook<'a, H>,
+    pub pool: &'a P,
+}
+
+impl<'a, H: ActionHandler, P: ThreadPool> ConcurrentTaskHook<'a, H, P> {
+    pub fn new(tasks: TaskHook<'a, H>, pool: &'a P) -> Self {
+        Self { tasks, pool }
+    }
+}
+
+impl<H, P> ExecutorHook for ConcurrentTaskHook<'_, H, P>
+where
+    H: ActionHandler,
+    H::Bb: SplitBlackboard,
+    H::Execute: Send,
+    H::Condition: Sync,
+    P: ThreadPool,
+{
+    fn hook(&mut self, leaf: &LeafNode) -> Status {
+        self.tasks.hook(leaf)
+    }
+
+    fn halt(&mut self, leaf: &LeafNode) {
+        self.tasks.halt(leaf);
+    }
+
+    fn pre_tick(&mut self, node_id: CTreeNodeID) -> PreTick {
+        self.tasks.pre_tick(node_id)
+    }
+
+    fn post_tick(&mut self, node_id: CTreeNodeID, status: Status) {
+        self.tasks.post_tick(node_id, status);
+    }
+
+    fn halt_control(&mut self, node_id: CTreeNodeID) {
+        self.tasks.halt_control(node_id);
+    }
+
+    fn select(&mut self, node_id: CTreeNodeID, children: usize) -> Option<Selection> {
+        self.tasks.select(node_id, children)
+    }
+
+    fn hook_concurrent(&mut self, leaves: &[&LeafNode]) -> Vec<Status> {
+        let TaskHook {
+            dispatch,
+            blackboard,
+        } = &mut self.tasks;
+        let read_only_check = dispatch.read_only_check();
+        let tasks = dispatch.tasks_mut(leaves);
+        let mut forks: Vec<H::Bb> = tasks
+            .iter()
+            .filter(|task| matches!(task, Some(TaskMut::Executor(_))))
+            .map(|_| blackboard.fork())
+            .collect();
+        let mut statuses = vec![Status::Failure; leaves.len()];
+
+        let shared: &H::Bb = blackboard;
+        let mut forks_iter = forks.iter_mut();
+        let jobs: Vec<Job<'_>> = tasks
+            .into_iter()
+            .zip(leaves)
+            .zip(statuses.iter_mut())
+            .filter_map(|((task, leaf), slot)| -> Option<Job<'_>> {
+                match task {
+                    Some(TaskMut::Executor(executor)) => {
+                        let fork = forks_iter.next()?;
+                        Some(Box::new(move || {
+                            *slot = tick_executor(executor, leaf, fork)
+                        }))
+                    }
+                    Some(TaskMut::Conditional(conditional)) => Some(Box::new(move || {
+                        *slot = tick_conditional(
+                            conditional,
+                            || conditional_name(leaf, conditional),
+                            shared,
+                            read_only_check,
+                        )
+                    })),
+                    None => {
+                        log::error!("Leaf id {:?} is not handled by this LeafMask", leaf.id);
+                        None
+                    }
+                }
+            })
+            .collect();
+        self.pool.scope(jobs);
+
+        blackboard.merge(forks);
+        statuses
+    }
+}
+
+impl<H, D, C> ShrubberyBT<H, D, C>
+where
+    H: ActionHandler,
+    H::Bb: SplitBlackboard,
+    H::Execute: Send,
+    H::Condition: Sync,
+    D: Decorator,
+    C: Composite,
+{
+    /// [`Self::tick`], running the leaves of concurrent parallel nodes on `pool`.
+    pub fn tick_concurrent<P: ThreadPool>(&mut self, blackboard: &mut H::Bb, pool: &P) -> Status {
+        let task_hook = TaskHook::new(&mut self.dispatch, blackboard);
+        self.control_tree
+            .tick(&mut ConcurrentTaskHook::new(task_hook, pool))
+    }
+
+    /// [`Self::run`], running the leaves of concurrent parallel nodes on `pool`.
+    pub fn run_concurrent<P: ThreadPool>(&mut self, blackboard: &mut H::Bb, pool: &P) -> Status {
+        let task_hook = TaskHook::new(&mut self.dispatch, blackboard);
+        self.control_tree
+            .run(&mut ConcurrentTaskHook::new(task_hook, pool))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Barrier};
+
+    use super::*;
+    use crate::prelude::ParallelPolicy;
+
+    /// Append-only log, every fork's new entries are appended on merge.
+    #[derive(Debug, Default, Clone, PartialEq)]
+    struct Log(Vec<&'static str>);
+
+    impl SplitBlackboard for Log {
+        fn merge(&mut self, forks: Vec<Self>) {
+            let seen = self.0.len();
+            for fork in forks {
+                self.0.extend_from_slice(&fork.0[seen..]);
+            }
+        }
+    }
+
+    /// Tracks how many [`Work`] executors are running at once.
+    #[derive(Debug, Default, Clone)]
+    struct Occupancy {
+        active: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+        /// Holds every executor until `n` of them are running, so they can only finish if they
+        /// run at the same time.
+        barrier: Option<Arc<Barrier>>,
+    }
+
+    impl Occupancy {
+        fn concurrent(n: usize) -> Self {
+            Self {
+                barrier: Some(Arc::new(Barrier::new(n))),
+                ..Default::default()
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct Work(&'static str, Occupancy);
+
+    impl Executor<Log> for Work {
+        fn execute(&self, blackboard: &mut Log) -> Status {
+            let active = self.1.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.1.peak.fetch_max(active, Ordering::SeqCst);
+            if let Some(barrier) = &self.1.barrier {
+                barrier.wait();
+            }
+            self.1.active.fetch_sub(1, Ordering::SeqCst);
+
+            blackboard.0.push(self.0);
+            Status::Success
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct IsEmpty;
+
+    impl Conditional<Log> for IsEmpty {
+        fn conditional(&self, blackboard: &Log) -> Status {
+            blackboard.0.is_empty().into()
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct WorkHandler;
+
+    impl ActionHandler for WorkHandler {
+        type Bb = Log;
+        type Execute = Work;
+        type Condition = IsEmpty;
+    }
+
+    fn work_bt(occupancy: &Occupancy) -> ShrubberyBT<WorkHandler> {
+        let mut builder = ShrubberyBT::<WorkHandler>::builder();
+        builder.layer_with_deps(occupancy.clone(), |occupancy, mut root| {
+            root.parallel_concurrent_with_deps(
+                occupancy,
+                ParallelPolicy::WaitAll,
+                |occupancy, mut parallel| {
+                    parallel.execute(Work("a", occupancy.clone()));
+                    parallel.execute(Work("b", occupancy));
+                    parallel.condition(IsEmpty);
+                },
+            );
+        });
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn leaves_run_concurrently() {
+        let occupancy = Occupancy::concurrent(2);
+        let mut bt = work_bt(&occupancy);
+        let mut log = Log::default();
+
+        assert_eq!(bt.run_concurrent(&mut log, &ScopedThreads), Status::Success);
+        assert_eq!(occupancy.peak.load(Ordering::SeqCst), 2);
+        assert_eq!(log, Log(vec!["a", "b"]));
+    }
+
+    #[test]
+    fn sequential_without_pool() {
+        let occupancy = Occupancy::default();
+        let mut bt = work_bt(&occupancy);
+        let mut log = Log::default();
+
+        // the condition sees the writes of the executors before it
+        assert_eq!(bt.run(&mut log), Status::Failure);
+        assert_eq!(occupancy.peak.load(Ordering::SeqCst), 1);
+    }
+}
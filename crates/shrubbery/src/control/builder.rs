@@ -1,3 +1,7 @@
+use std::time::Duration;
+
+use crate::clock::ClockHandle;
+use crate::rng::RngHandle;
 use crate::ShrubberyResult;
 
 use super::control_nodes::*;
@@ -6,40 +10,40 @@ use decorators::*;
 
 /// For building control trees
 #[derive(Clone)]
-pub struct CTreeBuilder<D: Decorator> {
-    pub(crate) inner: ControlTree<D>,
+pub struct CTreeBuilder<D: Decorator, C: Composite = StandardComposite> {
+    pub(crate) inner: ControlTree<D, C>,
 }
 
-impl<D: Decorator> ControlTree<D> {
-    pub fn into_builder(self) -> CTreeBuilder<D> {
+impl<D: Decorator, C: Composite> ControlTree<D, C> {
+    pub fn into_builder(self) -> CTreeBuilder<D, C> {
         CTreeBuilder::from(self)
     }
-    pub fn builder() -> CTreeBuilder<D> {
+    pub fn builder() -> CTreeBuilder<D, C> {
         CTreeBuilder::new()
     }
 }
 
-impl<D: Decorator> From<ControlTree<D>> for CTreeBuilder<D> {
-    fn from(value: ControlTree<D>) -> Self {
+impl<D: Decorator, C: Composite> From<ControlTree<D, C>> for CTreeBuilder<D, C> {
+    fn from(value: ControlTree<D, C>) -> Self {
         CTreeBuilder { inner: value }
     }
 }
 
-impl<D: Decorator> Default for CTreeBuilder<D> {
+impl<D: Decorator, C: Composite> Default for CTreeBuilder<D, C> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-pub type CTreeLayerFn<O, D> = fn(CTreeLayerBuilder<'_, D>) -> O; // -> LayerBuilder<'_, D>;
+pub type CTreeLayerFn<O, D, C = StandardComposite> = fn(CTreeLayerBuilder<'_, D, C>) -> O; // -> LayerBuilder<'_, D>;
 
-impl<D: Decorator> CTreeBuilder<D> {
+impl<D: Decorator, C: Composite> CTreeBuilder<D, C> {
     pub fn new() -> Self {
         Self {
             inner: ControlTree::new(),
         }
     }
-    pub fn layer<O>(&mut self, f: CTreeLayerFn<O, D>) -> O {
+    pub fn layer<O>(&mut self, f: CTreeLayerFn<O, D, C>) -> O {
         f(CTreeLayerBuilder {
             builder: self,
             layer_id: ROOT_ID,
@@ -54,7 +58,8 @@ impl<D: Decorator> CTreeBuilder<D> {
     /// - If a cycle is detected in the tree
     /// - If a control node is left dangling (missing leaf)
     /// - If a decorator has more than one child
-    pub fn build(self) -> ShrubberyResult<ControlTree<D>> {
+    /// - If a composite rejects its children
+    pub fn build(self) -> ShrubberyResult<ControlTree<D, C>> {
         // validate the tree
         self.inner.validate_bt_rules()?;
 
@@ -66,25 +71,23 @@ impl<D: Decorator> CTreeBuilder<D> {
     #[cfg(test)]
     pub fn inject_cycle(&mut self) {
         let parent = ROOT_ID;
-        let child = self
-            .inner
-            .add_child_unchecked(parent, ControlNode::sequence());
+        let child = self.inner.add_child_unchecked(parent, LeafNode::default());
         self.inner.tree.entry(child).or_default().push(parent);
     }
 }
 
-pub struct CTreeLayerBuilder<'a, D: Decorator> {
-    pub(crate) builder: &'a mut CTreeBuilder<D>,
+pub struct CTreeLayerBuilder<'a, D: Decorator, C: Composite = StandardComposite> {
+    pub(crate) builder: &'a mut CTreeBuilder<D, C>,
     pub layer_id: CTreeNodeID,
     pub layer_depth: usize,
 }
 
-impl<'a, D: Decorator> CTreeLayerBuilder<'a, D> {
+impl<'a, D: Decorator, C: Composite> CTreeLayerBuilder<'a, D, C> {
     /// create a new layer builder with depth 0
     pub fn new(
-        builder: &'a mut CTreeBuilder<D>,
+        builder: &'a mut CTreeBuilder<D, C>,
         layer_id: CTreeNodeID,
-    ) -> CTreeLayerBuilder<'a, D> {
+    ) -> CTreeLayerBuilder<'a, D, C> {
         Self {
             builder,
             layer_id,
@@ -92,22 +95,13 @@ impl<'a, D: Decorator> CTreeLayerBuilder<'a, D> {
         }
     }
 
-    /// Add a [`Sequence`] node, and build it's sub-tree
-    pub fn sequence<O>(&mut self, layer_fn: CTreeLayerFn<O, D>) -> O {
-        self.control_node(ControlNode::sequence(), layer_fn)
-    }
-
-    /// Add a [`Fallback`] node, and build it's sub-tree
-    pub fn fallback<O>(&mut self, layer_fn: CTreeLayerFn<O, D>) -> O {
-        self.control_node(ControlNode::fallback(), layer_fn)
-    }
-
-    /// Add a [`Parallel`] node, and build it's sub-tree
-    pub fn parallel<O>(&mut self, layer_fn: CTreeLayerFn<O, D>) -> O {
-        self.control_node(ControlNode::parallel(), layer_fn)
+    /// Add a user defined [`Composite`] node, and build it's sub-tree
+    pub fn composite<O>(&mut self, composite: impl Into<C>, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        let node = ControlNode::composite(composite.into());
+        self.control_node(node, layer_fn)
     }
 
-    pub fn decorator<O>(&mut self, decorator: impl Into<D>, layer_fn: CTreeLayerFn<O, D>) -> O {
+    pub fn decorator<O>(&mut self, decorator: impl Into<D>, layer_fn: CTreeLayerFn<O, D, C>) -> O {
         let node = ControlNode::decorator(decorator.into());
         self.control_node(node, layer_fn)
     }
@@ -116,7 +110,15 @@ impl<'a, D: Decorator> CTreeLayerBuilder<'a, D> {
         f(self)
     }
 
-    pub fn next_layer(&mut self, node: impl Into<ControlNode<D>>) -> CTreeLayerBuilder<'_, D> {
+    /// Set the [`Memory`] of the node this layer is adding children to
+    pub fn memory(&mut self, memory: Memory) {
+        self.builder.inner[self.layer_id].set_memory(memory);
+    }
+
+    pub fn next_layer(
+        &mut self,
+        node: impl Into<ControlNode<D, C>>,
+    ) -> CTreeLayerBuilder<'_, D, C> {
         let parent_id = self
             .builder
             .inner
@@ -132,8 +134,8 @@ impl<'a, D: Decorator> CTreeLayerBuilder<'a, D> {
     /// `layer_fn` provides a [`LayerBuilder`] for defining the subtree beneath the added `node`
     pub fn control_node<O>(
         &mut self,
-        node: impl Into<ControlNode<D>>,
-        layer_fn: CTreeLayerFn<O, D>,
+        node: impl Into<ControlNode<D, C>>,
+        layer_fn: CTreeLayerFn<O, D, C>,
     ) -> O {
         let layer_builder = self.next_layer(node);
         layer_fn(layer_builder)
@@ -153,24 +155,155 @@ impl<'a, D: Decorator> CTreeLayerBuilder<'a, D> {
     }
 }
 
-impl<'a, D: Decorator + From<StandardDecorator>> CTreeLayerBuilder<'a, D> {
-    pub fn repeat<O>(&mut self, retries: usize, layer_fn: CTreeLayerFn<O, D>) -> O {
+impl<'a, D: Decorator, C: Composite + From<StandardComposite>> CTreeLayerBuilder<'a, D, C> {
+    /// Add a [`Sequence`] node, and build it's sub-tree
+    pub fn sequence<O>(&mut self, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        self.control_node(ControlNode::sequence(), layer_fn)
+    }
+
+    /// Add a [`Fallback`] node, and build it's sub-tree
+    pub fn fallback<O>(&mut self, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        self.control_node(ControlNode::fallback(), layer_fn)
+    }
+
+    /// Add a [`Parallel`] node, and build it's sub-tree
+    pub fn parallel<O>(&mut self, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        self.control_node(ControlNode::parallel(), layer_fn)
+    }
+
+    /// Add a [`Parallel`] node with a [`ParallelPolicy`], and build it's sub-tree
+    pub fn parallel_policy<O>(
+        &mut self,
+        policy: ParallelPolicy,
+        layer_fn: CTreeLayerFn<O, D, C>,
+    ) -> O {
+        self.control_node(ControlNode::parallel_with_policy(policy), layer_fn)
+    }
+
+    /// Add a [`Parallel`] node that ticks its leaves concurrently, and build it's sub-tree
+    pub fn parallel_concurrent<O>(
+        &mut self,
+        policy: ParallelPolicy,
+        layer_fn: CTreeLayerFn<O, D, C>,
+    ) -> O {
+        self.control_node(ControlNode::parallel_concurrent(policy), layer_fn)
+    }
+
+    /// Add a [`ReactiveSequence`] node, and build it's sub-tree
+    pub fn reactive_sequence<O>(&mut self, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        self.control_node(ControlNode::reactive_sequence(), layer_fn)
+    }
+
+    /// Add a [`ReactiveFallback`] node, and build it's sub-tree
+    pub fn reactive_fallback<O>(&mut self, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        self.control_node(ControlNode::reactive_fallback(), layer_fn)
+    }
+
+    /// Add an [`IfThenElse`] node, and build it's sub-tree: the condition, then & else children
+    pub fn if_then_else<O>(&mut self, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        self.control_node(ControlNode::if_then_else(), layer_fn)
+    }
+
+    /// Add a [`WhileDoElse`] node, and build it's sub-tree: the condition, do & else children
+    pub fn while_do_else<O>(&mut self, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        self.control_node(ControlNode::while_do_else(), layer_fn)
+    }
+
+    /// Add a [`RandomSelector`] node, and build it's sub-tree
+    pub fn random_selector<O>(
+        &mut self,
+        rng: impl Into<RngHandle>,
+        layer_fn: CTreeLayerFn<O, D, C>,
+    ) -> O {
+        self.control_node(ControlNode::random_selector(rng), layer_fn)
+    }
+
+    /// Add a [`WeightedRandom`] node with a weight per child, and build it's sub-tree
+    pub fn weighted_random<O>(
+        &mut self,
+        weights: Vec<u32>,
+        rng: impl Into<RngHandle>,
+        layer_fn: CTreeLayerFn<O, D, C>,
+    ) -> O {
+        self.control_node(ControlNode::weighted_random(weights, rng), layer_fn)
+    }
+}
+
+impl<'a, D: Decorator + From<StandardDecorator>, C: Composite> CTreeLayerBuilder<'a, D, C> {
+    pub fn repeat<O>(&mut self, retries: usize, layer_fn: CTreeLayerFn<O, D, C>) -> O {
         let decorator = D::from(Repeater::new(retries).into());
         self.decorator(decorator, layer_fn)
     }
-    pub fn invert<O>(&mut self, layer_fn: CTreeLayerFn<O, D>) -> O {
+    pub fn invert<O>(&mut self, layer_fn: CTreeLayerFn<O, D, C>) -> O {
         let decorator = D::from(Inverter::default().into());
         self.decorator(decorator, layer_fn)
     }
 
-    pub fn subtree_named<O>(&mut self, name: &str, layer_fn: CTreeLayerFn<O, D>) -> O {
+    pub fn subtree_named<O>(&mut self, name: &str, layer_fn: CTreeLayerFn<O, D, C>) -> O {
         let decorator = D::from(Subtree::new(name.to_string()).into());
         self.decorator(decorator, layer_fn)
     }
-    pub fn subtree<O>(&mut self, layer_fn: CTreeLayerFn<O, D>) -> O {
+    pub fn subtree<O>(&mut self, layer_fn: CTreeLayerFn<O, D, C>) -> O {
         let decorator = D::from(Subtree::default().into());
         self.decorator(decorator, layer_fn)
     }
+    pub fn timeout<O>(
+        &mut self,
+        duration: Duration,
+        clock: impl Into<ClockHandle>,
+        layer_fn: CTreeLayerFn<O, D, C>,
+    ) -> O {
+        let decorator = D::from(Timeout::new(duration, clock).into());
+        self.decorator(decorator, layer_fn)
+    }
+    pub fn retry_with_backoff<O>(
+        &mut self,
+        retries: usize,
+        backoff: Backoff,
+        clock: impl Into<ClockHandle>,
+        layer_fn: CTreeLayerFn<O, D, C>,
+    ) -> O {
+        let decorator = D::from(RetryWithBackoff::new(retries, backoff, clock).into());
+        self.decorator(decorator, layer_fn)
+    }
+    pub fn cooldown<O>(
+        &mut self,
+        cooldown: Duration,
+        clock: impl Into<ClockHandle>,
+        layer_fn: CTreeLayerFn<O, D, C>,
+    ) -> O {
+        let decorator = D::from(Cooldown::new(cooldown, clock).into());
+        self.decorator(decorator, layer_fn)
+    }
+    pub fn repeat_n<O>(&mut self, n: usize, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        let decorator = D::from(RepeatN::new(n).into());
+        self.decorator(decorator, layer_fn)
+    }
+    pub fn retry_until_successful<O>(&mut self, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        let decorator = D::from(RetryUntilSuccessful::default().into());
+        self.decorator(decorator, layer_fn)
+    }
+    pub fn keep_running_until_failure<O>(&mut self, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        let decorator = D::from(KeepRunningUntilFailure::default().into());
+        self.decorator(decorator, layer_fn)
+    }
+    pub fn infinite<O>(&mut self, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        let decorator = D::from(Infinite::default().into());
+        self.decorator(decorator, layer_fn)
+    }
+    pub fn force_success<O>(&mut self, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        let decorator = D::from(ForceSuccess::default().into());
+        self.decorator(decorator, layer_fn)
+    }
+    pub fn force_failure<O>(&mut self, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        let decorator = D::from(ForceFailure::default().into());
+        self.decorator(decorator, layer_fn)
+    }
+    /// See [`RunOnce`] for returning a fixed status or surviving resets
+    pub fn run_once<O>(&mut self, run_once: RunOnce, layer_fn: CTreeLayerFn<O, D, C>) -> O {
+        let decorator = D::from(run_once.into());
+        self.decorator(decorator, layer_fn)
+    }
 }
 
 #[cfg(test)]
@@ -231,4 +364,37 @@ mod test {
 
         assert!(matches!(err, ShrubberyError::InvalidDecorator { .. }));
     }
+
+    #[test]
+    fn if_then_else_children_nobuild() {
+        let mut builder = ControlTree::<StandardDecorator>::builder();
+        builder.layer(|mut root_layer| {
+            // a condition with nothing to run
+            root_layer.if_then_else(|mut if_layer| {
+                if_layer.leaf_node(LeafNode::default());
+            });
+            root_layer.while_do_else(|mut while_layer| {
+                for _ in 0..4 {
+                    while_layer.leaf_node(LeafNode::default());
+                }
+            });
+        });
+        let err = builder.build().unwrap_err(); // panic if this isn't an error
+
+        assert!(matches!(err, ShrubberyError::InvalidComposite { .. }));
+
+        let mut builder = ControlTree::<StandardDecorator>::builder();
+        builder.layer(|mut root_layer| {
+            root_layer.if_then_else(|mut if_layer| {
+                if_layer.leaf_node(LeafNode::default());
+                if_layer.leaf_node(LeafNode::default());
+            });
+            root_layer.while_do_else(|mut while_layer| {
+                for _ in 0..3 {
+                    while_layer.leaf_node(LeafNode::default());
+                }
+            });
+        });
+        assert!(builder.build().is_ok());
+    }
 }
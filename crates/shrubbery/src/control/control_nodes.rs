@@ -1,55 +1,86 @@
+use std::time::Duration;
+
 use ahash::HashSet;
 
 use super::CTreeNodeID;
 use super::ChildUpdate;
+use crate::clock::ClockHandle;
+use crate::prelude::Backoff;
+use crate::prelude::Cooldown;
+use crate::prelude::ForceFailure;
+use crate::prelude::ForceSuccess;
+use crate::prelude::Guard;
+use crate::prelude::Infinite;
 use crate::prelude::Inverter;
+use crate::prelude::KeepRunningUntilFailure;
+use crate::prelude::RepeatN;
 use crate::prelude::Repeater;
+use crate::prelude::RetryUntilSuccessful;
+use crate::prelude::RetryWithBackoff;
+use crate::prelude::RunOnce;
 use crate::prelude::StandardDecorator;
 use crate::prelude::Subtree;
+use crate::prelude::Timeout;
+use crate::rng::RngHandle;
 use crate::traits::*;
 use crate::Status;
 
+use derive_more::From;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct ControlNode<D>
+pub struct ControlNode<D, C = StandardComposite>
 where
     D: Decorator,
+    C: Composite,
 {
-    pub node_type: ControlNodeType<D>,
+    pub node_type: ControlNodeType<D, C>,
     pub status: Option<Status>,
     pub id: Option<CTreeNodeID>,
+    /// Which children that already succeeded are ticked again
+    pub memory: Memory,
     pub(crate) reset_requests: Vec<CTreeNodeID>,
 }
 
-impl<D: Decorator> ControlNode<D> {
+impl<D: Decorator, C: Composite> ControlNode<D, C> {
     pub fn reset(&mut self) {
+        self.exit_if_running();
+        self.reset_requests.clear();
         match &mut self.node_type {
-            ControlNodeType::Sequence(s) => s.reset(),
-            ControlNodeType::Fallback(s) => s.reset(),
-            ControlNodeType::Parallel(s) => s.reset(),
+            ControlNodeType::Composite(c) => c.reset(),
             ControlNodeType::Decorator(s) => s.reset(),
         }
     }
-    pub fn sequence() -> Self {
-        Self {
-            node_type: Sequence::default().into(),
-            status: None,
-            id: None,
-            reset_requests: Default::default(),
+    /// [`Self::reset`] as part of resetting the whole tree, see [`Decorator::restart`].
+    pub fn restart(&mut self) {
+        self.exit_if_running();
+        match &mut self.node_type {
+            ControlNodeType::Decorator(d) => {
+                self.reset_requests.clear();
+                d.restart();
+            }
+            _ => self.reset(),
         }
     }
-    pub fn parallel() -> Self {
-        Self {
-            node_type: Parallel::default().into(),
-            status: None,
-            id: None,
-            reset_requests: Default::default(),
+    /// A running decorator is being reset, let it know it was halted, see [`Decorator::on_exit`]
+    fn exit_if_running(&mut self) {
+        if let ControlNodeType::Decorator(d) = &mut self.node_type {
+            if self.status.is_some_and(|s| s.is_running()) {
+                d.on_exit(Status::Running);
+            }
         }
     }
-    pub fn fallback() -> Self {
+    pub fn composite(composite: impl Into<C>) -> Self {
+        let composite = composite.into();
+        let memory = if composite.reevaluate_conditions() {
+            Memory::ReevaluateConditionsOnly
+        } else {
+            Memory::Remember
+        };
         Self {
-            node_type: Fallback::default().into(),
+            node_type: ControlNodeType::Composite(composite),
             status: None,
             id: None,
+            memory,
             reset_requests: Default::default(),
         }
     }
@@ -58,12 +89,86 @@ impl<D: Decorator> ControlNode<D> {
             node_type: ControlNodeType::Decorator(decorator.into()),
             status: None,
             id: None,
+            memory: Memory::Remember,
             reset_requests: Default::default(),
         }
     }
+    /// Tick the children that already succeeded according to `memory`, rather than the node's
+    /// default
+    pub fn memory(mut self, memory: Memory) -> Self {
+        self.memory = memory;
+        self
+    }
 }
 
-impl ControlNode<StandardDecorator> {
+impl<D: Decorator, C: Composite + From<StandardComposite>> ControlNode<D, C> {
+    pub fn sequence() -> Self {
+        Self::composite(StandardComposite::from(Sequence::default()))
+    }
+    pub fn parallel() -> Self {
+        Self::composite(StandardComposite::from(Parallel::default()))
+    }
+    pub fn parallel_with_policy(policy: ParallelPolicy) -> Self {
+        Self::composite(StandardComposite::from(Parallel::new(policy)))
+    }
+    /// [`Parallel`] that succeeds once `success` children succeed & fails once `failure`
+    /// children fail (`None` meaning all of them).
+    pub fn parallel_threshold(success: Option<usize>, failure: Option<usize>) -> Self {
+        Self::parallel_with_policy(ParallelPolicy::threshold(success, failure))
+    }
+    /// [`Parallel`] whose leaf children are ticked at the same time, see
+    /// [`Parallel::run_concurrently`].
+    pub fn parallel_concurrent(policy: ParallelPolicy) -> Self {
+        Self::composite(StandardComposite::from(
+            Parallel::new(policy).run_concurrently(),
+        ))
+    }
+    /// [`Parallel`] where the first child to finish decides the status.
+    pub fn parallel_race() -> Self {
+        Self::parallel_with_policy(ParallelPolicy::Race)
+    }
+    /// [`Parallel`] that succeeds as soon as one child succeeds, fails if all of them fail.
+    pub fn parallel_succeed_on_one() -> Self {
+        Self::parallel_with_policy(ParallelPolicy::succeed_on_one())
+    }
+    pub fn fallback() -> Self {
+        Self::composite(StandardComposite::from(Fallback::default()))
+    }
+    pub fn reactive_sequence() -> Self {
+        Self::composite(StandardComposite::from(ReactiveSequence::default()))
+    }
+    pub fn reactive_fallback() -> Self {
+        Self::composite(StandardComposite::from(ReactiveFallback::default()))
+    }
+    /// [`RandomSelector`] drawing from `rng`, e.g. [`RngHandle::seeded`]
+    pub fn random_selector(rng: impl Into<RngHandle>) -> Self {
+        Self::composite(StandardComposite::from(RandomSelector::new(rng)))
+    }
+    /// [`WeightedRandom`] with a weight per child, drawing from `rng`
+    pub fn weighted_random(weights: Vec<u32>, rng: impl Into<RngHandle>) -> Self {
+        Self::composite(StandardComposite::from(WeightedRandom::new(weights, rng)))
+    }
+    /// [`Switch`] whose child is picked by a [`BlackboardSelector`]
+    pub fn switch() -> Self {
+        Self::composite(StandardComposite::from(Switch::default()))
+    }
+    pub fn if_then_else() -> Self {
+        Self::composite(StandardComposite::from(IfThenElse::default()))
+    }
+    pub fn while_do_else() -> Self {
+        Self::composite(StandardComposite::from(WhileDoElse::default()))
+    }
+    /// [`UtilitySelector`] whose children are scored by a [`BlackboardSelector`], re-scored every
+    /// tick with `rescore`'s hysteresis if it's set
+    pub fn utility_selector(rescore: Option<f32>) -> Self {
+        Self::composite(StandardComposite::from(UtilitySelector {
+            rescore,
+            ..Default::default()
+        }))
+    }
+}
+
+impl<C: Composite> ControlNode<StandardDecorator, C> {
     pub fn inverter() -> Self {
         Self::decorator(Inverter::default())
     }
@@ -74,39 +179,89 @@ impl ControlNode<StandardDecorator> {
     pub fn subtree() -> Self {
         Self::decorator(Subtree::default())
     }
+    /// Fail the child once it has been running for `duration`, according to `clock`
+    pub fn timeout(duration: Duration, clock: impl Into<ClockHandle>) -> Self {
+        Self::decorator(Timeout::new(duration, clock))
+    }
+    /// Number of retries **after** the first failure, waiting `backoff` before each one
+    pub fn retry_with_backoff(
+        retries: usize,
+        backoff: Backoff,
+        clock: impl Into<ClockHandle>,
+    ) -> Self {
+        Self::decorator(RetryWithBackoff::new(retries, backoff, clock))
+    }
+    /// Fail without running the child until `cooldown` has passed since it last finished
+    pub fn cooldown(cooldown: Duration, clock: impl Into<ClockHandle>) -> Self {
+        Self::decorator(Cooldown::new(cooldown, clock))
+    }
+    /// Run the child `n` times, failing as soon as one run fails
+    pub fn repeat_n(n: usize) -> Self {
+        Self::decorator(RepeatN::new(n))
+    }
+    pub fn retry_until_successful() -> Self {
+        Self::decorator(RetryUntilSuccessful::default())
+    }
+    pub fn keep_running_until_failure() -> Self {
+        Self::decorator(KeepRunningUntilFailure::default())
+    }
+    pub fn infinite() -> Self {
+        Self::decorator(Infinite::default())
+    }
+    pub fn force_success() -> Self {
+        Self::decorator(ForceSuccess::default())
+    }
+    pub fn force_failure() -> Self {
+        Self::decorator(ForceFailure::default())
+    }
+    /// See [`RunOnce`] for returning a fixed status or surviving resets
+    pub fn run_once(run_once: RunOnce) -> Self {
+        Self::decorator(run_once)
+    }
+    /// Attachment point for a [`BlackboardDecorator`]
+    pub fn guard(name: Option<String>) -> Self {
+        Self::decorator(Guard::new(name))
+    }
 }
 
-impl<D: Decorator> Control for ControlNode<D> {
+impl<D: Decorator, C: Composite> Control for ControlNode<D, C> {
     fn tick(&mut self) -> Status {
         // First time this node has been ticked
+        let was_running = self.status.is_some_and(|s| s.is_running());
         if self.status.is_none() {
             self.status = Some(Status::Running);
+            if let ControlNodeType::Decorator(d) = &mut self.node_type {
+                d.init();
+            }
         }
         let status = match &mut self.node_type {
-            ControlNodeType::Sequence(seq) => seq.tick(),
-            ControlNodeType::Fallback(f) => f.tick(),
-            ControlNodeType::Parallel(p) => p.tick(),
-            ControlNodeType::Decorator(d) => d.status(),
+            ControlNodeType::Composite(c) => c.tick(),
+            ControlNodeType::Decorator(d) => {
+                let status = d.status();
+                match (was_running, status.is_running()) {
+                    (false, true) => d.on_enter(),
+                    (true, false) => d.on_exit(status),
+                    _ => {}
+                }
+                status
+            }
         };
         self.status = Some(status);
         status
     }
     fn child_updated(&mut self, update: ChildUpdate) {
         match &mut self.node_type {
-            ControlNodeType::Sequence(seq) => seq.child_updated(update),
-            ControlNodeType::Fallback(f) => f.child_updated(update),
-            ControlNodeType::Parallel(p) => p.child_updated(update),
+            ControlNodeType::Composite(c) => c.child_updated(update),
             ControlNodeType::Decorator(d) => {
-                self.status = Some(d.child_updated(update));
+                // the status is picked up by the tick, so `on_exit` sees the change
+                d.child_updated(update);
             }
         }
         self.tick();
     }
     fn all_children_seen(&mut self) {
         match &mut self.node_type {
-            ControlNodeType::Sequence(seq) => seq.all_children_seen(),
-            ControlNodeType::Fallback(f) => f.all_children_seen(),
-            ControlNodeType::Parallel(p) => p.all_children_seen(),
+            ControlNodeType::Composite(c) => c.all_children_seen(),
             ControlNodeType::Decorator(d) => {
                 if let Some(reset) = d.reset_request() {
                     self.reset_requests.push(reset);
@@ -117,30 +272,300 @@ impl<D: Decorator> Control for ControlNode<D> {
             }
         }
     }
+    fn yielded(&self) -> bool {
+        match &self.node_type {
+            ControlNodeType::Composite(c) => c.yielded(),
+            ControlNodeType::Decorator(d) => d.hold(),
+        }
+    }
+    fn reevaluate_conditions(&self) -> bool {
+        match &self.node_type {
+            ControlNodeType::Composite(c) => c.reevaluate_conditions(),
+            ControlNodeType::Decorator(_) => false,
+        }
+    }
+    fn runs_concurrently(&self) -> bool {
+        match &self.node_type {
+            ControlNodeType::Composite(c) => c.runs_concurrently(),
+            ControlNodeType::Decorator(_) => false,
+        }
+    }
+    fn visit_order(
+        &mut self,
+        children: &[CTreeNodeID],
+        selected: Option<Selection>,
+    ) -> Option<Vec<usize>> {
+        match &mut self.node_type {
+            ControlNodeType::Composite(c) => c.visit_order(children, selected),
+            ControlNodeType::Decorator(_) => None,
+        }
+    }
+    fn skip(&self, child: CTreeNodeID) -> bool {
+        match &self.node_type {
+            ControlNodeType::Composite(c) => c.skip(child),
+            ControlNodeType::Decorator(_) => false,
+        }
+    }
+    fn reevaluate(&self, child: CTreeNodeID) -> bool {
+        match &self.node_type {
+            ControlNodeType::Composite(c) => c.reevaluate(child),
+            ControlNodeType::Decorator(_) => false,
+        }
+    }
 }
 
 /// Defines the control flow of the BT.
 ///
 /// If a [`ControlNode`] reached during DFS returns [`Status::Running`]
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum ControlNodeType<D: Decorator> {
+pub enum ControlNodeType<D: Decorator, C: Composite = StandardComposite> {
+    /// Composites have any number of children, and define how the flow moves between them. Common
+    /// composites are provided in [`StandardComposite`]
+    Composite(C),
+
+    /// Decorators only have one child, and define custom policy. Common decorator policies are
+    /// provided in [`StandardDecorator`]
+    Decorator(D),
+}
+
+/// The built in [`Composite`]s
+#[derive(Debug, Clone, PartialEq, Eq, From)]
+pub enum StandardComposite {
     /// Run children in order, failing immediately if any child fails
     Sequence(Sequence),
 
     /// Run children in order until one succeeds, fails if all fail, succeeds if any succeed.
     Fallback(Fallback),
 
-    /// Run all children, regardless of their success or failure
+    /// Run all children at the same time, until the [`ParallelPolicy`] decides the outcome. By
+    /// default that is once every child has finished, regardless of their success or failure.
     Parallel(Parallel),
 
-    /// Decorators only have one child, and define custom policy. Common decorator policies are
-    /// provided in [`StandardDecorator`]
-    Decorator(D),
+    /// Like [`Sequence`], but conditional children are re-checked every tick while a later child
+    /// is running, and the running child is halted if one of them fails.
+    ReactiveSequence(ReactiveSequence),
+
+    /// Like [`Fallback`], but higher priority children are re-checked every tick while a later
+    /// child is running, and the running child is halted if one of them succeeds.
+    ReactiveFallback(ReactiveFallback),
+
+    /// Run the one child picked from the blackboard, see [`Switch`]
+    Switch(Switch),
+
+    /// Like [`Fallback`], but the children are tried in a random order
+    RandomSelector(RandomSelector),
+
+    /// Run one child picked at random by weight, see [`WeightedRandom`]
+    WeightedRandom(WeightedRandom),
+
+    /// Try the children best first, scored from the blackboard, see [`UtilitySelector`]
+    UtilitySelector(UtilitySelector),
+
+    /// Run the second child if the first succeeds, the third if it fails, see [`IfThenElse`]
+    IfThenElse(IfThenElse),
+
+    /// [`IfThenElse`] that checks its condition every tick, see [`WhileDoElse`]
+    WhileDoElse(WhileDoElse),
+}
+
+impl Control for StandardComposite {
+    fn tick(&mut self) -> Status {
+        match self {
+            StandardComposite::Sequence(seq) => seq.tick(),
+            StandardComposite::Fallback(f) => f.tick(),
+            StandardComposite::Parallel(p) => p.tick(),
+            StandardComposite::ReactiveSequence(seq) => seq.tick(),
+            StandardComposite::ReactiveFallback(f) => f.tick(),
+            StandardComposite::Switch(s) => s.tick(),
+            StandardComposite::RandomSelector(r) => r.tick(),
+            StandardComposite::WeightedRandom(w) => w.tick(),
+            StandardComposite::UtilitySelector(u) => u.tick(),
+            StandardComposite::IfThenElse(i) => i.tick(),
+            StandardComposite::WhileDoElse(w) => w.tick(),
+        }
+    }
+    fn child_updated(&mut self, update: ChildUpdate) {
+        match self {
+            StandardComposite::Sequence(seq) => seq.child_updated(update),
+            StandardComposite::Fallback(f) => f.child_updated(update),
+            StandardComposite::Parallel(p) => p.child_updated(update),
+            StandardComposite::ReactiveSequence(seq) => seq.child_updated(update),
+            StandardComposite::ReactiveFallback(f) => f.child_updated(update),
+            StandardComposite::Switch(s) => s.child_updated(update),
+            StandardComposite::RandomSelector(r) => r.child_updated(update),
+            StandardComposite::WeightedRandom(w) => w.child_updated(update),
+            StandardComposite::UtilitySelector(u) => u.child_updated(update),
+            StandardComposite::IfThenElse(i) => i.child_updated(update),
+            StandardComposite::WhileDoElse(w) => w.child_updated(update),
+        }
+    }
+    fn all_children_seen(&mut self) {
+        match self {
+            StandardComposite::Sequence(seq) => seq.all_children_seen(),
+            StandardComposite::Fallback(f) => f.all_children_seen(),
+            StandardComposite::Parallel(p) => p.all_children_seen(),
+            StandardComposite::ReactiveSequence(seq) => seq.all_children_seen(),
+            StandardComposite::ReactiveFallback(f) => f.all_children_seen(),
+            StandardComposite::Switch(s) => s.all_children_seen(),
+            StandardComposite::RandomSelector(r) => r.all_children_seen(),
+            StandardComposite::WeightedRandom(w) => w.all_children_seen(),
+            StandardComposite::UtilitySelector(u) => u.all_children_seen(),
+            StandardComposite::IfThenElse(i) => i.all_children_seen(),
+            StandardComposite::WhileDoElse(w) => w.all_children_seen(),
+        }
+    }
+    fn yielded(&self) -> bool {
+        match self {
+            StandardComposite::ReactiveSequence(seq) => seq.yielded(),
+            StandardComposite::ReactiveFallback(f) => f.yielded(),
+            StandardComposite::UtilitySelector(u) => u.yielded(),
+            StandardComposite::IfThenElse(i) => i.yielded(),
+            StandardComposite::WhileDoElse(w) => w.yielded(),
+            _ => false,
+        }
+    }
+    fn reevaluate_conditions(&self) -> bool {
+        match self {
+            StandardComposite::ReactiveSequence(seq) => seq.reevaluate_conditions(),
+            StandardComposite::ReactiveFallback(f) => f.reevaluate_conditions(),
+            StandardComposite::WhileDoElse(w) => w.reevaluate_conditions(),
+            _ => false,
+        }
+    }
+    fn runs_concurrently(&self) -> bool {
+        match self {
+            StandardComposite::Parallel(p) => p.runs_concurrently(),
+            _ => false,
+        }
+    }
+    fn visit_order(
+        &mut self,
+        children: &[CTreeNodeID],
+        selected: Option<Selection>,
+    ) -> Option<Vec<usize>> {
+        match self {
+            StandardComposite::Switch(s) => s.visit_order(children, selected),
+            StandardComposite::RandomSelector(r) => r.visit_order(children, selected),
+            StandardComposite::WeightedRandom(w) => w.visit_order(children, selected),
+            StandardComposite::UtilitySelector(u) => u.visit_order(children, selected),
+            StandardComposite::IfThenElse(i) => i.visit_order(children, selected),
+            StandardComposite::WhileDoElse(w) => w.visit_order(children, selected),
+            _ => None,
+        }
+    }
+    fn skip(&self, child: CTreeNodeID) -> bool {
+        match self {
+            StandardComposite::IfThenElse(i) => i.skip(child),
+            StandardComposite::WhileDoElse(w) => w.skip(child),
+            StandardComposite::Parallel(p) => p.skip(child),
+            _ => false,
+        }
+    }
+    fn reevaluate(&self, child: CTreeNodeID) -> bool {
+        match self {
+            StandardComposite::WhileDoElse(w) => w.reevaluate(child),
+            _ => false,
+        }
+    }
+}
+
+impl Composite for StandardComposite {
+    fn reset(&mut self) {
+        match self {
+            StandardComposite::Sequence(seq) => seq.reset(),
+            StandardComposite::Fallback(f) => f.reset(),
+            StandardComposite::Parallel(p) => p.reset(),
+            StandardComposite::ReactiveSequence(seq) => seq.reset(),
+            StandardComposite::ReactiveFallback(f) => f.reset(),
+            StandardComposite::Switch(s) => s.reset(),
+            StandardComposite::RandomSelector(r) => r.reset(),
+            StandardComposite::WeightedRandom(w) => w.reset(),
+            StandardComposite::UtilitySelector(u) => u.reset(),
+            StandardComposite::IfThenElse(i) => i.reset(),
+            StandardComposite::WhileDoElse(w) => w.reset(),
+        }
+    }
+    fn name(&self) -> String {
+        match self {
+            StandardComposite::Sequence(_) => "Sequence",
+            StandardComposite::Fallback(_) => "Fallback",
+            StandardComposite::Parallel(_) => "Parallel",
+            StandardComposite::ReactiveSequence(_) => "ReactiveSequence",
+            StandardComposite::ReactiveFallback(_) => "ReactiveFallback",
+            StandardComposite::Switch(_) => "Switch",
+            StandardComposite::RandomSelector(_) => "RandomSelector",
+            StandardComposite::WeightedRandom(_) => "WeightedRandom",
+            StandardComposite::UtilitySelector(_) => "UtilitySelector",
+            StandardComposite::IfThenElse(_) => "IfThenElse",
+            StandardComposite::WhileDoElse(_) => "WhileDoElse",
+        }
+        .to_string()
+    }
+    fn details(&self) -> Option<String> {
+        match self {
+            StandardComposite::Parallel(p) => {
+                let concurrent = if p.concurrent { " concurrent" } else { "" };
+                Some(format!("{:?}{concurrent}", p.policy))
+            }
+            StandardComposite::Switch(s) => s.active.map(|active| format!("case {active}")),
+            StandardComposite::RandomSelector(r) => Some(format!("order {:?}", r.order)),
+            StandardComposite::WeightedRandom(w) => Some(match w.picked {
+                Some(picked) => format!("weights {:?}, picked {picked}", w.weights),
+                None => format!("weights {:?}", w.weights),
+            }),
+            StandardComposite::UtilitySelector(u) => Some(format!("scores {:?}", u.scores)),
+            _ => None,
+        }
+    }
+    fn validate(&self, children: &[CTreeNodeID]) -> Result<(), String> {
+        match self {
+            StandardComposite::WeightedRandom(w) if w.weights.len() != children.len() => {
+                Err(format!(
+                    "{} weights for {} children",
+                    w.weights.len(),
+                    children.len()
+                ))
+            }
+            StandardComposite::IfThenElse(_) | StandardComposite::WhileDoElse(_)
+                if !(2..=3).contains(&children.len()) =>
+            {
+                Err(format!(
+                    "{} needs a condition, a then & optionally an else child, found {} children",
+                    self.name(),
+                    children.len()
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<D: Decorator, C: Composite> ControlNode<D, C> {
+    pub fn try_as_composite(&self) -> Option<&C> {
+        if let ControlNodeType::Composite(c) = &self.node_type {
+            Some(c)
+        } else {
+            None
+        }
+    }
+    pub fn is_composite(&self) -> bool {
+        self.try_as_composite().is_some()
+    }
+    pub fn try_as_decorator(&self) -> Option<&D> {
+        if let ControlNodeType::Decorator(d) = &self.node_type {
+            Some(d)
+        } else {
+            None
+        }
+    }
+    pub fn is_decorator(&self) -> bool {
+        self.try_as_decorator().is_some()
+    }
 }
 
 impl<D: Decorator> ControlNode<D> {
     pub fn try_as_sequence(&self) -> Option<&Sequence> {
-        if let ControlNodeType::Sequence(s) = &self.node_type {
+        if let Some(StandardComposite::Sequence(s)) = self.try_as_composite() {
             Some(s)
         } else {
             None
@@ -150,7 +575,7 @@ impl<D: Decorator> ControlNode<D> {
         self.try_as_sequence().is_some()
     }
     pub fn try_as_fallback(&self) -> Option<&Fallback> {
-        if let ControlNodeType::Fallback(f) = &self.node_type {
+        if let Some(StandardComposite::Fallback(f)) = self.try_as_composite() {
             Some(f)
         } else {
             None
@@ -160,7 +585,7 @@ impl<D: Decorator> ControlNode<D> {
         self.try_as_fallback().is_some()
     }
     pub fn try_as_parallel(&self) -> Option<&Parallel> {
-        if let ControlNodeType::Parallel(p) = &self.node_type {
+        if let Some(StandardComposite::Parallel(p)) = self.try_as_composite() {
             Some(p)
         } else {
             None
@@ -169,32 +594,25 @@ impl<D: Decorator> ControlNode<D> {
     pub fn is_parallel(&self) -> bool {
         self.try_as_parallel().is_some()
     }
-    pub fn try_as_decorator(&self) -> Option<&D> {
-        if let ControlNodeType::Decorator(d) = &self.node_type {
-            Some(d)
+    pub fn try_as_reactive_sequence(&self) -> Option<&ReactiveSequence> {
+        if let Some(StandardComposite::ReactiveSequence(s)) = self.try_as_composite() {
+            Some(s)
         } else {
             None
         }
     }
-    pub fn is_decorator(&self) -> bool {
-        self.try_as_decorator().is_some()
+    pub fn is_reactive_sequence(&self) -> bool {
+        self.try_as_reactive_sequence().is_some()
     }
-}
-
-impl<D: Decorator> From<Sequence> for ControlNodeType<D> {
-    fn from(seq: Sequence) -> Self {
-        ControlNodeType::Sequence(seq)
-    }
-}
-
-impl<D: Decorator> From<Fallback> for ControlNodeType<D> {
-    fn from(fallback: Fallback) -> Self {
-        ControlNodeType::Fallback(fallback)
+    pub fn try_as_reactive_fallback(&self) -> Option<&ReactiveFallback> {
+        if let Some(StandardComposite::ReactiveFallback(f)) = self.try_as_composite() {
+            Some(f)
+        } else {
+            None
+        }
     }
-}
-impl<D: Decorator> From<Parallel> for ControlNodeType<D> {
-    fn from(parallel: Parallel) -> Self {
-        ControlNodeType::Parallel(parallel)
+    pub fn is_reactive_fallback(&self) -> bool {
+        self.try_as_reactive_fallback().is_some()
     }
 }
 
@@ -259,11 +677,15 @@ impl Control for Sequence {
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub struct Fallback {
     pub status: Option<Status>,
+    /// A child returned [`Status::Running`] during the current traversal, so the fallback can't
+    /// fail yet.
+    pub running: bool,
 }
 
 impl Fallback {
     pub fn reset(&mut self) {
         self.status = None;
+        self.running = false;
     }
 }
 
@@ -273,16 +695,70 @@ impl Control for Fallback {
     }
 
     fn child_updated(&mut self, update: ChildUpdate) {
-        if update.status.is_success() {
-            self.status = Some(Status::Success);
+        match update.status {
+            Status::Success => self.status = Some(Status::Success),
+            Status::Running => self.running = true,
+            Status::Failure => {}
         }
     }
 
     fn all_children_seen(&mut self) {
-        self.status = self
-            .status
-            .map(|s| s.into_failure_if_running())
-            .or(Some(Status::Failure));
+        if !self.status.is_some_and(|s| s.is_success()) {
+            self.status = if self.running {
+                Some(Status::Running)
+            } else {
+                Some(Status::Failure)
+            };
+        }
+        self.running = false;
+    }
+}
+
+/// Decides which children a [`ControlNode`] ticks again once they've succeeded, while the node is
+/// still running.
+///
+/// Composites default to [`Memory::Remember`], or [`Memory::ReevaluateConditionsOnly`] if they
+/// [re-evaluate their conditions](Control::reevaluate_conditions).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Memory {
+    /// Don't tick children that already succeeded until the node is reset.
+    #[default]
+    Remember,
+
+    /// Tick every child again each traversal, branches that already succeeded are started over.
+    Reevaluate,
+
+    /// Tick [`Conditional`] leaves again each traversal, remember everything else.
+    ReevaluateConditionsOnly,
+}
+
+/// Decides when a [`Parallel`] node has finished.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParallelPolicy {
+    /// Run every child to completion, succeed if they all succeeded, fail otherwise.
+    #[default]
+    WaitAll,
+
+    /// M-of-N policy. Succeed once `success` children have succeeded, fail once `failure`
+    /// children have failed, or once it's impossible to reach the success threshold. `None`
+    /// means all the children.
+    Threshold {
+        success: Option<usize>,
+        failure: Option<usize>,
+    },
+
+    /// The first child to finish decides the status.
+    Race,
+}
+
+impl ParallelPolicy {
+    pub fn threshold(success: Option<usize>, failure: Option<usize>) -> Self {
+        ParallelPolicy::Threshold { success, failure }
+    }
+
+    /// Succeed as soon as one child succeeds, fail only if all of them fail.
+    pub fn succeed_on_one() -> Self {
+        Self::threshold(Some(1), None)
     }
 }
 
@@ -291,53 +767,683 @@ pub struct Parallel {
     pub success: HashSet<CTreeNodeID>,
     pub failure: HashSet<CTreeNodeID>,
     pub pending: HashSet<CTreeNodeID>,
-    pub finished: bool,
+    pub policy: ParallelPolicy,
+    /// Set once the [`ParallelPolicy`] has decided the outcome, the remaining children are then
+    /// halted.
+    pub status: Option<Status>,
+    /// Leaf children are ticked as one batch through [`ExecutorHook::hook_concurrent`].
+    pub concurrent: bool,
 }
 
 impl Parallel {
+    pub fn new(policy: ParallelPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// The first child to finish decides the status.
+    pub fn race() -> Self {
+        Self::new(ParallelPolicy::Race)
+    }
+
+    /// Succeed as soon as one child succeeds, fail only if all of them fail.
+    pub fn succeed_on_one() -> Self {
+        Self::new(ParallelPolicy::succeed_on_one())
+    }
+
+    /// Tick the leaf children in one batch through [`ExecutorHook::hook_concurrent`], so a hook
+    /// like [`ConcurrentTaskHook`](crate::concurrent::ConcurrentTaskHook) can run them on
+    /// separate threads. Control children are still ticked in order afterwards.
+    pub fn run_concurrently(mut self) -> Self {
+        self.concurrent = true;
+        self
+    }
+
+    /// Succeed once `m` children have succeeded.
+    pub fn with_success_threshold(mut self, m: usize) -> Self {
+        let failure = match self.policy {
+            ParallelPolicy::Threshold { failure, .. } => failure,
+            _ => None,
+        };
+        self.policy = ParallelPolicy::threshold(Some(m), failure);
+        self
+    }
+
+    /// Fail once `n` children have failed.
+    pub fn with_failure_threshold(mut self, n: usize) -> Self {
+        let success = match self.policy {
+            ParallelPolicy::Threshold { success, .. } => success,
+            _ => None,
+        };
+        self.policy = ParallelPolicy::threshold(success, Some(n));
+        self
+    }
+
     pub fn reset(&mut self) {
         self.success.clear();
         self.failure.clear();
         self.pending.clear();
-        self.finished = false;
+        self.status = None;
+    }
+
+    /// Apply the [`ParallelPolicy`]. The total number of children is only known once they have
+    /// all been seen, so thresholds that depend on it wait for `all_seen`.
+    fn decide(&mut self, all_seen: bool) {
+        if self.status.is_some() {
+            return;
+        }
+        let succeeded = self.success.len();
+        let failed = self.failure.len();
+        let children = succeeded + failed + self.pending.len();
+
+        self.status = match self.policy {
+            ParallelPolicy::WaitAll => {
+                if all_seen && self.pending.is_empty() {
+                    Some((failed == 0).into())
+                } else {
+                    None
+                }
+            }
+            ParallelPolicy::Threshold { success, failure } => {
+                if success.is_some_and(|m| succeeded >= m) {
+                    Some(Status::Success)
+                } else if failure.is_some_and(|n| failed >= n) {
+                    Some(Status::Failure)
+                } else if all_seen {
+                    let success = success.unwrap_or(children);
+                    let failure = failure.unwrap_or(children);
+                    if succeeded >= success {
+                        Some(Status::Success)
+                    } else if failed >= failure || children - failed < success {
+                        Some(Status::Failure)
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                }
+            }
+            // decided as soon as a child finishes
+            ParallelPolicy::Race => None,
+        };
     }
 }
 
 impl Control for Parallel {
-    /// All child nodes are to run, regardless of success/failure state.
+    /// All child nodes are to run until the [`ParallelPolicy`] decides the outcome.
     fn tick(&mut self) -> Status {
-        //
-        if self.finished {
-            if self.failure.is_empty() {
-                Status::Success
-            } else {
-                Status::Failure
-            }
-        } else {
-            Status::Running
-        }
+        self.status.unwrap_or_default()
     }
 
     fn child_updated(&mut self, update: ChildUpdate) {
         let ChildUpdate { status, child_id } = update;
+        self.success.remove(&child_id);
+        self.failure.remove(&child_id);
+        self.pending.remove(&child_id);
         match status {
             Status::Success => {
-                self.pending.remove(&child_id);
                 self.success.insert(child_id);
             }
             Status::Failure => {
-                self.pending.remove(&child_id);
                 self.failure.insert(child_id);
             }
             Status::Running => {
                 self.pending.insert(child_id);
             }
         }
+        if self.policy == ParallelPolicy::Race && status.is_terminal() && self.status.is_none() {
+            self.status = Some(status);
+        }
+        self.decide(false);
     }
 
     fn all_children_seen(&mut self) {
-        if self.pending.is_empty() {
-            self.finished = true;
+        self.decide(true);
+    }
+
+    fn runs_concurrently(&self) -> bool {
+        self.concurrent
+    }
+
+    /// A failed child has already been counted against the [`ParallelPolicy`], so it isn't
+    /// ticked again until the node is reset. Successful children follow the [`Memory`] policy.
+    fn skip(&self, child: CTreeNodeID) -> bool {
+        self.failure.contains(&child)
+    }
+}
+
+/// [`Sequence`] that re-ticks its [`Conditional`] children every traversal.
+///
+/// The traversal stops at the first running child, so on the next tick every guard in front of
+/// it is checked again before it is. If a guard fails the sequence fails & the running child is
+/// halted.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct ReactiveSequence {
+    pub status: Option<Status>,
+    /// The child that returned [`Status::Running`] during the current traversal.
+    pub running: Option<CTreeNodeID>,
+}
+
+impl ReactiveSequence {
+    pub fn reset(&mut self) {
+        self.status = None;
+        self.running = None;
+    }
+}
+
+impl Control for ReactiveSequence {
+    fn tick(&mut self) -> Status {
+        self.status.unwrap_or_default()
+    }
+
+    fn child_updated(&mut self, update: ChildUpdate) {
+        match update.status {
+            Status::Success => {}
+            Status::Failure => self.status = Some(Status::Failure),
+            Status::Running => self.running = Some(update.child_id),
         }
     }
+
+    fn all_children_seen(&mut self) {
+        if !self.status.is_some_and(|s| s.is_failure()) {
+            self.status = if self.running.is_some() {
+                Some(Status::Running)
+            } else {
+                Some(Status::Success)
+            };
+        }
+        self.running = None;
+    }
+
+    fn yielded(&self) -> bool {
+        self.running.is_some()
+    }
+
+    fn reevaluate_conditions(&self) -> bool {
+        true
+    }
+}
+
+/// [`Fallback`] that re-ticks its [`Conditional`] children every traversal.
+///
+/// The traversal stops at the first running child, so on the next tick every higher priority
+/// child is tried again before it is. If one of them succeeds the fallback succeeds & the
+/// running child is halted.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct ReactiveFallback {
+    pub status: Option<Status>,
+    /// The child that returned [`Status::Running`] during the current traversal.
+    pub running: Option<CTreeNodeID>,
+}
+
+impl ReactiveFallback {
+    pub fn reset(&mut self) {
+        self.status = None;
+        self.running = None;
+    }
+}
+
+impl Control for ReactiveFallback {
+    fn tick(&mut self) -> Status {
+        self.status.unwrap_or_default()
+    }
+
+    fn child_updated(&mut self, update: ChildUpdate) {
+        match update.status {
+            Status::Success => self.status = Some(Status::Success),
+            Status::Failure => {}
+            Status::Running => self.running = Some(update.child_id),
+        }
+    }
+
+    fn all_children_seen(&mut self) {
+        if !self.status.is_some_and(|s| s.is_success()) {
+            self.status = if self.running.is_some() {
+                Some(Status::Running)
+            } else {
+                Some(Status::Failure)
+            };
+        }
+        self.running = None;
+    }
+
+    fn yielded(&self) -> bool {
+        self.running.is_some()
+    }
+
+    fn reevaluate_conditions(&self) -> bool {
+        true
+    }
+}
+
+/// Ticks exactly one of its children, picked by a [`BlackboardSelector`] every traversal, and
+/// finishes with it. Fails if nothing was picked.
+///
+/// When the pick changes while the previous child is still running, that child is halted.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Switch {
+    pub status: Option<Status>,
+    /// Index of the child picked by the last traversal
+    pub active: Option<usize>,
+}
+
+impl Switch {
+    pub fn reset(&mut self) {
+        self.status = None;
+        self.active = None;
+    }
+}
+
+impl Control for Switch {
+    fn tick(&mut self) -> Status {
+        self.status.unwrap_or_default()
+    }
+
+    fn child_updated(&mut self, update: ChildUpdate) {
+        self.status = Some(update.status);
+    }
+
+    fn visit_order(
+        &mut self,
+        _children: &[CTreeNodeID],
+        selected: Option<Selection>,
+    ) -> Option<Vec<usize>> {
+        self.active = selected.and_then(|s| s.order.first().copied());
+        if self.active.is_none() {
+            self.status = Some(Status::Failure);
+        }
+        Some(self.active.into_iter().collect())
+    }
+}
+
+/// A [`Fallback`] that tries its children in a shuffled order, shuffled again every time it
+/// starts over.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RandomSelector {
+    pub rng: RngHandle,
+    /// The order (by index) the children are being tried in
+    pub order: Vec<usize>,
+    pub fallback: Fallback,
+}
+
+impl RandomSelector {
+    pub fn new(rng: impl Into<RngHandle>) -> Self {
+        Self {
+            rng: rng.into(),
+            order: vec![],
+            fallback: Fallback::default(),
+        }
+    }
+    pub fn reset(&mut self) {
+        self.order.clear();
+        self.fallback.reset();
+    }
+}
+
+impl Control for RandomSelector {
+    fn tick(&mut self) -> Status {
+        self.fallback.tick()
+    }
+
+    fn child_updated(&mut self, update: ChildUpdate) {
+        self.fallback.child_updated(update)
+    }
+
+    fn all_children_seen(&mut self) {
+        self.fallback.all_children_seen()
+    }
+
+    fn visit_order(
+        &mut self,
+        children: &[CTreeNodeID],
+        _selected: Option<Selection>,
+    ) -> Option<Vec<usize>> {
+        if self.order.len() != children.len() {
+            self.order = (0..children.len()).collect();
+            self.rng.with(|rng| rng.shuffle(&mut self.order));
+        }
+        Some(self.order.clone())
+    }
+}
+
+/// Runs a single child, picked at random in proportion to its weight, and finishes with it. The
+/// pick is kept until the node is reset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeightedRandom {
+    pub rng: RngHandle,
+    /// Weight of each child, in order
+    pub weights: Vec<u32>,
+    /// Index of the picked child
+    pub picked: Option<usize>,
+    pub status: Option<Status>,
+}
+
+impl WeightedRandom {
+    pub fn new(weights: Vec<u32>, rng: impl Into<RngHandle>) -> Self {
+        Self {
+            rng: rng.into(),
+            weights,
+            picked: None,
+            status: None,
+        }
+    }
+    pub fn reset(&mut self) {
+        self.picked = None;
+        self.status = None;
+    }
+    fn pick(&self) -> Option<usize> {
+        let total: u64 = self.weights.iter().map(|&w| w as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = self.rng.with(|rng| rng.below(total));
+        self.weights.iter().position(|&w| {
+            let hit = roll < w as u64;
+            roll = roll.saturating_sub(w as u64);
+            hit
+        })
+    }
+}
+
+impl Control for WeightedRandom {
+    fn tick(&mut self) -> Status {
+        self.status.unwrap_or_default()
+    }
+
+    fn child_updated(&mut self, update: ChildUpdate) {
+        self.status = Some(update.status);
+    }
+
+    fn visit_order(
+        &mut self,
+        _children: &[CTreeNodeID],
+        _selected: Option<Selection>,
+    ) -> Option<Vec<usize>> {
+        if self.picked.is_none() {
+            self.picked = self.pick();
+        }
+        if self.picked.is_none() {
+            self.status = Some(Status::Failure);
+        }
+        Some(self.picked.into_iter().collect())
+    }
+}
+
+/// Tries its children best first, by the scores a [`BlackboardSelector`] gives them, moving on to
+/// the next best when one fails. Succeeds with the first child that succeeds, fails if they all
+/// fail.
+///
+/// The children are scored when the node starts, and on every tick after that if it
+/// [rescores](Self::rescore). The child it's committed to is only dropped for one that beats it by
+/// more than the hysteresis, so close scores don't make it flip back & forth.
+#[derive(Debug, Default, Clone)]
+pub struct UtilitySelector {
+    pub status: Option<Status>,
+    /// Score of each child, from the last time they were scored
+    pub scores: Vec<f32>,
+    /// The order (by index) the children are being tried in
+    pub order: Vec<usize>,
+    /// Children (by index) that already failed
+    pub failed: Vec<usize>,
+    /// Re-score every tick, switching when another child's score beats the current one by more
+    /// than this
+    pub rescore: Option<f32>,
+    children: Vec<CTreeNodeID>,
+    running: bool,
+}
+
+impl UtilitySelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Re-score the children every tick, with `hysteresis`
+    pub fn rescore(mut self, hysteresis: f32) -> Self {
+        self.rescore = Some(hysteresis);
+        self
+    }
+    pub fn reset(&mut self) {
+        self.status = None;
+        self.order.clear();
+        self.failed.clear();
+        self.running = false;
+    }
+    /// The child (by index) currently being run, the best scoring one that hasn't failed
+    pub fn current(&self) -> Option<usize> {
+        self.order
+            .iter()
+            .copied()
+            .find(|i| !self.failed.contains(i))
+    }
+    /// Take the new ranking, unless the current pick is still within the hysteresis of the best
+    fn rank(&mut self, selection: Selection, hysteresis: f32) {
+        let Selection { mut order, scores } = selection;
+        if let (Some(current), Some(&best)) = (self.current(), order.first()) {
+            let score = |i: usize| scores.get(i).copied().unwrap_or(f32::MIN);
+            if best != current && score(best) <= score(current) + hysteresis {
+                order.retain(|&i| i != current);
+                order.insert(0, current);
+            }
+        }
+        self.order = order;
+        self.scores = scores;
+    }
+}
+
+impl Control for UtilitySelector {
+    fn tick(&mut self) -> Status {
+        self.status.unwrap_or_default()
+    }
+
+    fn child_updated(&mut self, update: ChildUpdate) {
+        match update.status {
+            Status::Success => self.status = Some(Status::Success),
+            Status::Running => self.running = true,
+            Status::Failure => {
+                if let Some(i) = self.children.iter().position(|&c| c == update.child_id) {
+                    self.failed.push(i);
+                }
+            }
+        }
+    }
+
+    fn all_children_seen(&mut self) {
+        if !self.status.is_some_and(|s| s.is_success()) {
+            self.status = if self.running {
+                Some(Status::Running)
+            } else {
+                Some(Status::Failure)
+            };
+        }
+        self.running = false;
+    }
+
+    fn yielded(&self) -> bool {
+        self.running
+    }
+
+    fn visit_order(
+        &mut self,
+        children: &[CTreeNodeID],
+        selected: Option<Selection>,
+    ) -> Option<Vec<usize>> {
+        self.children = children.to_vec();
+        let selection =
+            selected.unwrap_or_else(|| Selection::ordered((0..children.len()).collect()));
+        match self.rescore {
+            _ if self.order.is_empty() => self.rank(selection, 0.0),
+            Some(hysteresis) => self.rank(selection, hysteresis),
+            None => {}
+        }
+        Some(
+            self.order
+                .iter()
+                .copied()
+                .filter(|i| !self.failed.contains(i))
+                .collect(),
+        )
+    }
+}
+
+impl PartialEq for UtilitySelector {
+    fn eq(&self, other: &Self) -> bool {
+        let bits = |scores: &[f32]| scores.iter().map(|s| s.to_bits()).collect::<Vec<_>>();
+        self.status == other.status
+            && bits(&self.scores) == bits(&other.scores)
+            && self.order == other.order
+            && self.failed == other.failed
+            && self.rescore.map(f32::to_bits) == other.rescore.map(f32::to_bits)
+            && self.children == other.children
+            && self.running == other.running
+    }
+}
+
+// scores are compared bit for bit
+impl Eq for UtilitySelector {}
+
+/// Branches on its first child: runs the second child if it succeeds, the third if it fails, and
+/// finishes with the status of the branch it ran. Without a third child a failed condition fails
+/// the node.
+///
+/// Unlike a [`Fallback`] of a [`Sequence`] & an else branch, the else branch doesn't run when the
+/// then branch fails. The condition is only checked when the node starts, see [`WhileDoElse`] to
+/// keep checking it.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct IfThenElse {
+    pub status: Option<Status>,
+    /// The branch (by index, 1 or 2) the condition picked
+    pub branch: Option<usize>,
+    /// The condition returned [`Status::Running`] during the current traversal.
+    pub waiting: bool,
+    children: Vec<CTreeNodeID>,
+}
+
+impl IfThenElse {
+    pub fn reset(&mut self) {
+        self.status = None;
+        self.branch = None;
+        self.waiting = false;
+    }
+}
+
+impl Control for IfThenElse {
+    fn tick(&mut self) -> Status {
+        self.status.unwrap_or_default()
+    }
+
+    fn child_updated(&mut self, update: ChildUpdate) {
+        match self.children.iter().position(|&c| c == update.child_id) {
+            Some(0) => match update.status {
+                Status::Success => self.branch = Some(1),
+                Status::Failure if self.children.len() > 2 => self.branch = Some(2),
+                Status::Failure => self.status = Some(Status::Failure),
+                Status::Running => self.waiting = true,
+            },
+            _ if update.status.is_terminal() => self.status = Some(update.status),
+            _ => {}
+        }
+    }
+
+    fn all_children_seen(&mut self) {
+        self.waiting = false;
+    }
+
+    fn yielded(&self) -> bool {
+        self.waiting
+    }
+
+    fn visit_order(
+        &mut self,
+        children: &[CTreeNodeID],
+        _selected: Option<Selection>,
+    ) -> Option<Vec<usize>> {
+        self.children = children.to_vec();
+        // the condition isn't checked again once a branch is running
+        self.branch.map(|branch| vec![branch])
+    }
+
+    fn skip(&self, child: CTreeNodeID) -> bool {
+        let index = self.children.iter().position(|&c| c == child);
+        index != Some(0) && index != self.branch
+    }
+}
+
+/// [`IfThenElse`] that checks its condition again every tick, while its branch is running.
+///
+/// When the condition flips, the running branch is halted & the other one started. If the
+/// condition is still running, so is the node, & neither branch is ticked. The condition is
+/// started over every tick once it has finished, so it can be a whole branch (e.g. a [`Sequence`]
+/// of checks) as well as a [`Conditional`] leaf.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct WhileDoElse {
+    pub status: Option<Status>,
+    /// The branch (by index, 1 or 2) the condition picked on the last tick
+    pub branch: Option<usize>,
+    /// The condition returned [`Status::Running`] during the current traversal.
+    pub waiting: bool,
+    children: Vec<CTreeNodeID>,
+}
+
+impl WhileDoElse {
+    pub fn reset(&mut self) {
+        self.status = None;
+        self.branch = None;
+        self.waiting = false;
+    }
+}
+
+impl Control for WhileDoElse {
+    fn tick(&mut self) -> Status {
+        self.status.unwrap_or_default()
+    }
+
+    fn child_updated(&mut self, update: ChildUpdate) {
+        match self.children.iter().position(|&c| c == update.child_id) {
+            Some(0) => match update.status {
+                Status::Success => self.branch = Some(1),
+                Status::Failure if self.children.len() > 2 => self.branch = Some(2),
+                Status::Failure => {
+                    self.branch = None;
+                    self.status = Some(Status::Failure);
+                }
+                Status::Running => {
+                    self.branch = None;
+                    self.waiting = true;
+                }
+            },
+            _ if update.status.is_terminal() => self.status = Some(update.status),
+            _ => {}
+        }
+    }
+
+    fn all_children_seen(&mut self) {
+        self.waiting = false;
+    }
+
+    fn yielded(&self) -> bool {
+        self.waiting
+    }
+
+    fn reevaluate_conditions(&self) -> bool {
+        true
+    }
+
+    fn visit_order(
+        &mut self,
+        children: &[CTreeNodeID],
+        _selected: Option<Selection>,
+    ) -> Option<Vec<usize>> {
+        self.children = children.to_vec();
+        None
+    }
+
+    fn skip(&self, child: CTreeNodeID) -> bool {
+        let index = self.children.iter().position(|&c| c == child);
+        index != Some(0) && index != self.branch
+    }
+
+    fn reevaluate(&self, child: CTreeNodeID) -> bool {
+        self.children.first() == Some(&child)
+    }
 }
@@ -1,5 +1,9 @@
+use std::time::Duration;
+
 use super::CTreeNodeID;
 use super::ChildUpdate;
+use crate::clock::ClockHandle;
+use crate::rng::{Rng, SplitMix64};
 use crate::traits::*;
 use crate::Status;
 
@@ -15,6 +19,39 @@ pub enum StandardDecorator {
 
     /// Marker to indicate the branch is a subtree.
     Subtree(Subtree),
+
+    /// Fail the child if it has been running for longer than [`Timeout::duration`]
+    Timeout(Timeout),
+
+    /// Re-run the child after it fails, waiting a [`Backoff`] delay between attempts
+    RetryWithBackoff(RetryWithBackoff),
+
+    /// Fail without running the child if it finished less than [`Cooldown::cooldown`] ago
+    Cooldown(Cooldown),
+
+    /// Run the child [`RepeatN::n`] times, every run has to succeed
+    RepeatN(RepeatN),
+
+    /// Re-run the child until it succeeds, however many attempts it takes
+    RetryUntilSuccessful(RetryUntilSuccessful),
+
+    /// Re-run the child for as long as it succeeds, fails once it fails
+    KeepRunningUntilFailure(KeepRunningUntilFailure),
+
+    /// Re-run the child forever, whatever its result
+    Infinite(Infinite),
+
+    /// Succeed once the child finishes, whatever its result
+    ForceSuccess(ForceSuccess),
+
+    /// Fail once the child finishes, whatever its result
+    ForceFailure(ForceFailure),
+
+    /// Run the child once, then keep returning the same result without running it
+    RunOnce(RunOnce),
+
+    /// Attachment point for a [`BlackboardDecorator`], passes the child's status through
+    Guard(Guard),
 }
 
 impl StandardDecorator {
@@ -28,6 +65,43 @@ impl StandardDecorator {
     pub fn subtree() -> Self {
         Subtree::default().into()
     }
+    pub fn timeout(duration: Duration, clock: impl Into<ClockHandle>) -> Self {
+        Timeout::new(duration, clock).into()
+    }
+    pub fn retry_with_backoff(
+        retries: usize,
+        backoff: Backoff,
+        clock: impl Into<ClockHandle>,
+    ) -> Self {
+        RetryWithBackoff::new(retries, backoff, clock).into()
+    }
+    pub fn cooldown(cooldown: Duration, clock: impl Into<ClockHandle>) -> Self {
+        Cooldown::new(cooldown, clock).into()
+    }
+    pub fn repeat_n(n: usize) -> Self {
+        RepeatN::new(n).into()
+    }
+    pub fn retry_until_successful() -> Self {
+        RetryUntilSuccessful::default().into()
+    }
+    pub fn keep_running_until_failure() -> Self {
+        KeepRunningUntilFailure::default().into()
+    }
+    pub fn infinite() -> Self {
+        Infinite::default().into()
+    }
+    pub fn force_success() -> Self {
+        ForceSuccess::default().into()
+    }
+    pub fn force_failure() -> Self {
+        ForceFailure::default().into()
+    }
+    pub fn run_once(run_once: RunOnce) -> Self {
+        run_once.into()
+    }
+    pub fn guard(name: Option<String>) -> Self {
+        Guard::new(name).into()
+    }
 }
 
 impl Decorator for StandardDecorator {
@@ -36,6 +110,17 @@ impl Decorator for StandardDecorator {
             StandardDecorator::Invert(i) => i.child_updated(update),
             StandardDecorator::Repeat(r) => r.child_updated(update),
             StandardDecorator::Subtree(s) => s.child_updated(update),
+            StandardDecorator::Timeout(t) => t.child_updated(update),
+            StandardDecorator::RetryWithBackoff(r) => r.child_updated(update),
+            StandardDecorator::Cooldown(c) => c.child_updated(update),
+            StandardDecorator::RepeatN(r) => r.child_updated(update),
+            StandardDecorator::RetryUntilSuccessful(r) => r.child_updated(update),
+            StandardDecorator::KeepRunningUntilFailure(k) => k.child_updated(update),
+            StandardDecorator::Infinite(i) => i.child_updated(update),
+            StandardDecorator::ForceSuccess(f) => f.child_updated(update),
+            StandardDecorator::ForceFailure(f) => f.child_updated(update),
+            StandardDecorator::RunOnce(r) => r.child_updated(update),
+            StandardDecorator::Guard(g) => g.child_updated(update),
         }
     }
     fn init(&mut self) {
@@ -43,6 +128,17 @@ impl Decorator for StandardDecorator {
             StandardDecorator::Invert(i) => i.init(),
             StandardDecorator::Repeat(r) => r.init(),
             StandardDecorator::Subtree(s) => s.init(),
+            StandardDecorator::Timeout(t) => t.init(),
+            StandardDecorator::RetryWithBackoff(r) => r.init(),
+            StandardDecorator::Cooldown(c) => c.init(),
+            StandardDecorator::RepeatN(r) => r.init(),
+            StandardDecorator::RetryUntilSuccessful(r) => r.init(),
+            StandardDecorator::KeepRunningUntilFailure(k) => k.init(),
+            StandardDecorator::Infinite(i) => i.init(),
+            StandardDecorator::ForceSuccess(f) => f.init(),
+            StandardDecorator::ForceFailure(f) => f.init(),
+            StandardDecorator::RunOnce(r) => r.init(),
+            StandardDecorator::Guard(g) => g.init(),
         }
     }
     fn status(&self) -> Status {
@@ -50,6 +146,17 @@ impl Decorator for StandardDecorator {
             StandardDecorator::Invert(i) => i.status(),
             StandardDecorator::Repeat(r) => r.status(),
             StandardDecorator::Subtree(s) => s.status(),
+            StandardDecorator::Timeout(t) => t.status(),
+            StandardDecorator::RetryWithBackoff(r) => r.status(),
+            StandardDecorator::Cooldown(c) => c.status(),
+            StandardDecorator::RepeatN(r) => r.status(),
+            StandardDecorator::RetryUntilSuccessful(r) => r.status(),
+            StandardDecorator::KeepRunningUntilFailure(k) => k.status(),
+            StandardDecorator::Infinite(i) => i.status(),
+            StandardDecorator::ForceSuccess(f) => f.status(),
+            StandardDecorator::ForceFailure(f) => f.status(),
+            StandardDecorator::RunOnce(r) => r.status(),
+            StandardDecorator::Guard(g) => g.status(),
         }
     }
     fn reset(&mut self) {
@@ -57,6 +164,29 @@ impl Decorator for StandardDecorator {
             StandardDecorator::Invert(i) => i.reset(),
             StandardDecorator::Repeat(r) => r.reset(),
             StandardDecorator::Subtree(s) => s.reset(),
+            StandardDecorator::Timeout(t) => t.reset(),
+            StandardDecorator::RetryWithBackoff(r) => r.reset(),
+            StandardDecorator::Cooldown(c) => c.reset(),
+            StandardDecorator::RepeatN(r) => r.reset(),
+            StandardDecorator::RetryUntilSuccessful(r) => r.reset(),
+            StandardDecorator::KeepRunningUntilFailure(k) => k.reset(),
+            StandardDecorator::Infinite(i) => i.reset(),
+            StandardDecorator::ForceSuccess(f) => f.reset(),
+            StandardDecorator::ForceFailure(f) => f.reset(),
+            StandardDecorator::RunOnce(r) => r.reset(),
+            StandardDecorator::Guard(g) => g.reset(),
+        }
+    }
+    fn hold(&self) -> bool {
+        match self {
+            StandardDecorator::RetryWithBackoff(r) => r.hold(),
+            _ => false,
+        }
+    }
+    fn restart(&mut self) {
+        match self {
+            StandardDecorator::RunOnce(r) => r.restart(),
+            _ => self.reset(),
         }
     }
     fn reset_request(&mut self) -> Option<CTreeNodeID> {
@@ -64,6 +194,17 @@ impl Decorator for StandardDecorator {
             StandardDecorator::Invert(i) => i.reset_request(),
             StandardDecorator::Repeat(r) => r.reset_request(),
             StandardDecorator::Subtree(s) => s.reset_request(),
+            StandardDecorator::Timeout(t) => t.reset_request(),
+            StandardDecorator::RetryWithBackoff(r) => r.reset_request(),
+            StandardDecorator::Cooldown(c) => c.reset_request(),
+            StandardDecorator::RepeatN(r) => r.reset_request(),
+            StandardDecorator::RetryUntilSuccessful(r) => r.reset_request(),
+            StandardDecorator::KeepRunningUntilFailure(k) => k.reset_request(),
+            StandardDecorator::Infinite(i) => i.reset_request(),
+            StandardDecorator::ForceSuccess(f) => f.reset_request(),
+            StandardDecorator::ForceFailure(f) => f.reset_request(),
+            StandardDecorator::RunOnce(r) => r.reset_request(),
+            StandardDecorator::Guard(g) => g.reset_request(),
         }
     }
     fn name(&self) -> String {
@@ -71,6 +212,17 @@ impl Decorator for StandardDecorator {
             StandardDecorator::Invert(i) => i.name(),
             StandardDecorator::Repeat(r) => r.name(),
             StandardDecorator::Subtree(s) => s.name(),
+            StandardDecorator::Timeout(t) => t.name(),
+            StandardDecorator::RetryWithBackoff(r) => r.name(),
+            StandardDecorator::Cooldown(c) => c.name(),
+            StandardDecorator::RepeatN(r) => r.name(),
+            StandardDecorator::RetryUntilSuccessful(r) => r.name(),
+            StandardDecorator::KeepRunningUntilFailure(k) => k.name(),
+            StandardDecorator::Infinite(i) => i.name(),
+            StandardDecorator::ForceSuccess(f) => f.name(),
+            StandardDecorator::ForceFailure(f) => f.name(),
+            StandardDecorator::RunOnce(r) => r.name(),
+            StandardDecorator::Guard(g) => g.name(),
         }
     }
     fn details(&self) -> Option<String> {
@@ -78,6 +230,17 @@ impl Decorator for StandardDecorator {
             StandardDecorator::Invert(i) => Some(format!("{i:#?}")),
             StandardDecorator::Repeat(r) => Some(format!("{r:#?}")),
             StandardDecorator::Subtree(s) => Some(format!("{s:#?}")),
+            StandardDecorator::Timeout(t) => Some(format!("{t:#?}")),
+            StandardDecorator::RetryWithBackoff(r) => Some(format!("{r:#?}")),
+            StandardDecorator::Cooldown(c) => Some(format!("{c:#?}")),
+            StandardDecorator::RepeatN(r) => Some(format!("{r:#?}")),
+            StandardDecorator::RetryUntilSuccessful(r) => Some(format!("{r:#?}")),
+            StandardDecorator::KeepRunningUntilFailure(k) => Some(format!("{k:#?}")),
+            StandardDecorator::Infinite(i) => Some(format!("{i:#?}")),
+            StandardDecorator::ForceSuccess(f) => Some(format!("{f:#?}")),
+            StandardDecorator::ForceFailure(f) => Some(format!("{f:#?}")),
+            StandardDecorator::RunOnce(r) => Some(format!("{r:#?}")),
+            StandardDecorator::Guard(g) => Some(format!("{g:#?}")),
         }
     }
 }
@@ -190,8 +353,9 @@ impl Decorator for Repeater {
                 Status::Running
             }
             Status::Running => {
+                // the child is still going, don't reset it out from under itself
                 self.status = Some(Status::Running);
-                self.reset_request = Some(update.child_id);
+                self.reset_request = None;
                 Status::Running
             }
         }
@@ -222,3 +386,579 @@ impl Decorator for Repeater {
         format!("Repeat({})", self.retry)
     }
 }
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Timeout {
+    /// How long the child is allowed to keep running
+    pub duration: Duration,
+
+    /// Where the time comes from
+    pub clock: ClockHandle,
+
+    /// When the child was first seen running
+    pub started: Option<Duration>,
+
+    /// The current status
+    pub status: Option<Status>,
+
+    /// The child to halt once it has timed out.
+    pub reset_request: Option<CTreeNodeID>,
+}
+
+impl Timeout {
+    pub fn new(duration: Duration, clock: impl Into<ClockHandle>) -> Self {
+        Self {
+            duration,
+            clock: clock.into(),
+            started: None,
+            status: None,
+            reset_request: None,
+        }
+    }
+
+    /// Time left before the child times out, `None` if it isn't running.
+    pub fn remaining(&self) -> Option<Duration> {
+        let started = self.started?;
+        Some(
+            self.duration
+                .saturating_sub(self.clock.now().saturating_sub(started)),
+        )
+    }
+}
+
+impl Decorator for Timeout {
+    fn child_updated(&mut self, update: ChildUpdate) -> Status {
+        let status = match update.status {
+            Status::Running => {
+                let now = self.clock.now();
+                let started = *self.started.get_or_insert(now);
+                if now.saturating_sub(started) >= self.duration {
+                    // out of time, the running child gets halted
+                    self.reset_request = Some(update.child_id);
+                    Status::Failure
+                } else {
+                    Status::Running
+                }
+            }
+            status => status,
+        };
+        self.status = Some(status);
+        status
+    }
+    fn init(&mut self) {}
+    fn status(&self) -> Status {
+        self.status.unwrap_or_default()
+    }
+    fn reset_request(&mut self) -> Option<CTreeNodeID> {
+        self.reset_request.take()
+    }
+    fn reset(&mut self) {
+        self.started = None;
+        self.status = None;
+        self.reset_request = None;
+    }
+    fn name(&self) -> String {
+        format!("Timeout({:?})", self.duration)
+    }
+}
+
+/// How long [`RetryWithBackoff`] waits before each retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backoff {
+    /// The same delay every time
+    Fixed(Duration),
+
+    /// `initial + attempt * step`
+    Linear { initial: Duration, step: Duration },
+
+    /// `initial * factor^attempt`, capped at `max`
+    Exponential {
+        initial: Duration,
+        factor: u32,
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    /// Delay before retry number `attempt`, counting from `0`
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let attempt = u32::try_from(attempt).unwrap_or(u32::MAX);
+        match *self {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Linear { initial, step } => {
+                initial.saturating_add(step.saturating_mul(attempt))
+            }
+            Backoff::Exponential {
+                initial,
+                factor,
+                max,
+            } => initial
+                .saturating_mul(factor.saturating_pow(attempt))
+                .min(max),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RetryWithBackoff {
+    /// How many retries are allowed (not including the first attempt)
+    pub retries: usize,
+
+    /// How many retries have been used
+    pub attempt: usize,
+
+    /// Delay between attempts
+    pub backoff: Backoff,
+
+    /// Up to this much is randomly added to each delay
+    pub jitter: Duration,
+
+    /// Source of the jitter
+    pub rng: SplitMix64,
+
+    /// Where the time comes from
+    pub clock: ClockHandle,
+
+    /// When the child may be run again
+    pub retry_at: Option<Duration>,
+
+    /// The current status
+    pub status: Option<Status>,
+
+    /// The child to reset if it failed.
+    pub reset_request: Option<CTreeNodeID>,
+}
+
+impl RetryWithBackoff {
+    /// Number of retries **after** the first failure
+    pub fn new(retries: usize, backoff: Backoff, clock: impl Into<ClockHandle>) -> Self {
+        Self {
+            retries,
+            attempt: 0,
+            backoff,
+            jitter: Duration::ZERO,
+            rng: SplitMix64::default(),
+            clock: clock.into(),
+            retry_at: None,
+            status: None,
+            reset_request: None,
+        }
+    }
+
+    /// Randomly add up to `jitter` to each delay, drawn from an RNG seeded with `seed`
+    pub fn with_jitter(mut self, jitter: Duration, seed: u64) -> Self {
+        self.jitter = jitter;
+        self.rng = SplitMix64::new(seed);
+        self
+    }
+
+    /// Time left before the next retry, `None` if it isn't waiting.
+    pub fn remaining(&self) -> Option<Duration> {
+        let retry_at = self.retry_at?;
+        Some(retry_at.saturating_sub(self.clock.now())).filter(|r| !r.is_zero())
+    }
+}
+
+impl Decorator for RetryWithBackoff {
+    fn child_updated(&mut self, update: ChildUpdate) -> Status {
+        self.retry_at = None;
+        let status = match update.status {
+            Status::Failure if self.attempt < self.retries => {
+                let max_jitter = u64::try_from(self.jitter.as_nanos()).unwrap_or(u64::MAX);
+                let jitter = self.rng.below(max_jitter.saturating_add(1));
+                let delay = self
+                    .backoff
+                    .delay(self.attempt)
+                    .saturating_add(Duration::from_nanos(jitter));
+                self.attempt += 1;
+                self.retry_at = Some(self.clock.now().saturating_add(delay));
+                self.reset_request = Some(update.child_id);
+                Status::Running
+            }
+            status => status,
+        };
+        self.status = Some(status);
+        status
+    }
+    fn init(&mut self) {}
+    fn status(&self) -> Status {
+        self.status.unwrap_or_default()
+    }
+    fn hold(&self) -> bool {
+        self.remaining().is_some()
+    }
+    fn reset_request(&mut self) -> Option<CTreeNodeID> {
+        self.reset_request.take()
+    }
+    fn reset(&mut self) {
+        self.attempt = 0;
+        self.retry_at = None;
+        self.status = None;
+        self.reset_request = None;
+    }
+    fn name(&self) -> String {
+        format!("RetryWithBackoff({})", self.retries - self.attempt)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cooldown {
+    /// How long after the child finishes before it can be run again
+    pub cooldown: Duration,
+
+    /// Where the time comes from
+    pub clock: ClockHandle,
+
+    /// When the child last finished, kept across resets
+    pub last_run: Option<Duration>,
+
+    /// The current status
+    pub status: Option<Status>,
+}
+
+impl Cooldown {
+    pub fn new(cooldown: Duration, clock: impl Into<ClockHandle>) -> Self {
+        Self {
+            cooldown,
+            clock: clock.into(),
+            last_run: None,
+            status: None,
+        }
+    }
+
+    /// Time left before the child can be run again, `None` if it can be run now.
+    pub fn remaining(&self) -> Option<Duration> {
+        let elapsed = self.clock.now().saturating_sub(self.last_run?);
+        Some(self.cooldown.saturating_sub(elapsed)).filter(|r| !r.is_zero())
+    }
+}
+
+impl Decorator for Cooldown {
+    fn child_updated(&mut self, update: ChildUpdate) -> Status {
+        if update.status.is_terminal() {
+            self.last_run = Some(self.clock.now());
+        }
+        self.status = Some(update.status);
+        update.status
+    }
+    fn init(&mut self) {}
+    fn status(&self) -> Status {
+        match self.status {
+            Some(status) => status,
+            None if self.remaining().is_some() => Status::Failure,
+            None => Status::Running,
+        }
+    }
+    fn reset(&mut self) {
+        self.status = None;
+    }
+    fn name(&self) -> String {
+        format!("Cooldown({:?})", self.cooldown)
+    }
+}
+
+/// Shared bookkeeping for the looping decorators: count finished iterations & ask for the child
+/// to be reset before the next one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Iterations {
+    /// How many times the child has finished
+    pub count: usize,
+
+    /// The current status
+    pub status: Option<Status>,
+
+    /// The child to reset before the next iteration.
+    pub reset_request: Option<CTreeNodeID>,
+}
+
+impl Iterations {
+    /// The child finished, go round again.
+    fn again(&mut self, child_id: CTreeNodeID) -> Status {
+        self.count += 1;
+        self.reset_request = Some(child_id);
+        self.finish(Status::Running)
+    }
+
+    fn finish(&mut self, status: Status) -> Status {
+        self.status = Some(status);
+        status
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RepeatN {
+    /// How many successful runs are required
+    pub n: usize,
+
+    pub iterations: Iterations,
+}
+
+impl RepeatN {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            iterations: Iterations::default(),
+        }
+    }
+}
+
+impl Decorator for RepeatN {
+    fn child_updated(&mut self, update: ChildUpdate) -> Status {
+        match update.status {
+            Status::Success if self.iterations.count + 1 < self.n => {
+                self.iterations.again(update.child_id)
+            }
+            status => self.iterations.finish(status),
+        }
+    }
+    fn init(&mut self) {}
+    fn status(&self) -> Status {
+        if self.n == 0 {
+            // nothing to repeat
+            return Status::Success;
+        }
+        self.iterations.status.unwrap_or_default()
+    }
+    fn reset_request(&mut self) -> Option<CTreeNodeID> {
+        self.iterations.reset_request.take()
+    }
+    fn reset(&mut self) {
+        self.iterations.reset();
+    }
+    fn name(&self) -> String {
+        format!("RepeatN({}/{})", self.iterations.count, self.n)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RetryUntilSuccessful {
+    pub iterations: Iterations,
+}
+
+impl Decorator for RetryUntilSuccessful {
+    fn child_updated(&mut self, update: ChildUpdate) -> Status {
+        match update.status {
+            Status::Failure => self.iterations.again(update.child_id),
+            status => self.iterations.finish(status),
+        }
+    }
+    fn init(&mut self) {}
+    fn status(&self) -> Status {
+        self.iterations.status.unwrap_or_default()
+    }
+    fn reset_request(&mut self) -> Option<CTreeNodeID> {
+        self.iterations.reset_request.take()
+    }
+    fn reset(&mut self) {
+        self.iterations.reset();
+    }
+    fn name(&self) -> String {
+        format!("RetryUntilSuccessful({})", self.iterations.count)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeepRunningUntilFailure {
+    pub iterations: Iterations,
+}
+
+impl Decorator for KeepRunningUntilFailure {
+    fn child_updated(&mut self, update: ChildUpdate) -> Status {
+        match update.status {
+            Status::Success => self.iterations.again(update.child_id),
+            status => self.iterations.finish(status),
+        }
+    }
+    fn init(&mut self) {}
+    fn status(&self) -> Status {
+        self.iterations.status.unwrap_or_default()
+    }
+    fn reset_request(&mut self) -> Option<CTreeNodeID> {
+        self.iterations.reset_request.take()
+    }
+    fn reset(&mut self) {
+        self.iterations.reset();
+    }
+    fn name(&self) -> String {
+        format!("KeepRunningUntilFailure({})", self.iterations.count)
+    }
+}
+
+/// Never finishes on its own, the branch has to be halted from above.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Infinite {
+    pub iterations: Iterations,
+}
+
+impl Decorator for Infinite {
+    fn child_updated(&mut self, update: ChildUpdate) -> Status {
+        match update.status {
+            Status::Running => self.iterations.finish(Status::Running),
+            _ => self.iterations.again(update.child_id),
+        }
+    }
+    fn init(&mut self) {}
+    fn status(&self) -> Status {
+        Status::Running
+    }
+    fn reset_request(&mut self) -> Option<CTreeNodeID> {
+        self.iterations.reset_request.take()
+    }
+    fn reset(&mut self) {
+        self.iterations.reset();
+    }
+    fn name(&self) -> String {
+        format!("Infinite({})", self.iterations.count)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ForceSuccess {
+    child_status: Option<Status>,
+}
+
+impl Decorator for ForceSuccess {
+    fn child_updated(&mut self, update: ChildUpdate) -> Status {
+        self.child_status = Some(update.status);
+        self.status()
+    }
+    fn init(&mut self) {}
+    fn status(&self) -> Status {
+        match self.child_status {
+            Some(status) if status.is_terminal() => Status::Success,
+            _ => Status::Running,
+        }
+    }
+    fn reset(&mut self) {
+        self.child_status = None;
+    }
+    fn name(&self) -> String {
+        "ForceSuccess".to_string()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ForceFailure {
+    child_status: Option<Status>,
+}
+
+impl Decorator for ForceFailure {
+    fn child_updated(&mut self, update: ChildUpdate) -> Status {
+        self.child_status = Some(update.status);
+        self.status()
+    }
+    fn init(&mut self) {}
+    fn status(&self) -> Status {
+        match self.child_status {
+            Some(status) if status.is_terminal() => Status::Failure,
+            _ => Status::Running,
+        }
+    }
+    fn reset(&mut self) {
+        self.child_status = None;
+    }
+    fn name(&self) -> String {
+        "ForceFailure".to_string()
+    }
+}
+
+/// Runs its child once, then keeps returning its result (or [`Self::then`]) while the branch is
+/// reset, e.g. by a [`RepeatN`] above it.
+///
+/// NOTE: Without [`Self::persist`] the result is forgotten when the whole tree is reset, which
+/// [`ControlTree::tick`](crate::control::ControlTree::tick) does every time the tree finishes.
+/// Unless the tree is still running when it's ticked again, the child runs on every tick.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RunOnce {
+    /// Returned instead of the cached result once the child has run
+    pub then: Option<Status>,
+
+    /// Keep the result when the whole tree is reset too, so the child only ever runs once
+    pub persist: bool,
+
+    /// What the child returned the one time it ran
+    pub result: Option<Status>,
+
+    /// The current status
+    pub status: Option<Status>,
+}
+
+impl RunOnce {
+    /// Return `status` rather than the cached result on later ticks
+    pub fn then(mut self, status: Status) -> Self {
+        self.then = Some(status);
+        self
+    }
+
+    /// Remember the result across whole tree resets, not just resets of the branch
+    pub fn persist(mut self) -> Self {
+        self.persist = true;
+        self
+    }
+}
+
+impl Decorator for RunOnce {
+    fn child_updated(&mut self, update: ChildUpdate) -> Status {
+        if update.status.is_terminal() {
+            self.result = Some(update.status);
+        }
+        self.status = Some(update.status);
+        update.status
+    }
+    fn init(&mut self) {}
+    fn status(&self) -> Status {
+        match (self.status, self.result) {
+            (Some(status), _) => status,
+            (None, Some(result)) => self.then.unwrap_or(result),
+            (None, None) => Status::Running,
+        }
+    }
+    fn reset(&mut self) {
+        self.status = None;
+    }
+    fn restart(&mut self) {
+        self.status = None;
+        if !self.persist {
+            self.result = None;
+        }
+    }
+    fn name(&self) -> String {
+        "RunOnce".to_string()
+    }
+}
+
+/// Passes the child's status straight through. The decision to run the child at all is made by
+/// the [`BlackboardDecorator`] attached to this node in the
+/// [`LeafDispatch`](crate::executor_mask::LeafDispatch).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Guard {
+    status: Option<Status>,
+    name: Option<String>,
+}
+
+impl Guard {
+    pub fn new(name: Option<String>) -> Self {
+        Self { status: None, name }
+    }
+}
+
+impl Decorator for Guard {
+    fn child_updated(&mut self, update: ChildUpdate) -> Status {
+        self.status = Some(update.status);
+        update.status
+    }
+    fn init(&mut self) {}
+    fn status(&self) -> Status {
+        self.status.unwrap_or_default()
+    }
+    fn reset(&mut self) {
+        self.status = None;
+    }
+    fn name(&self) -> String {
+        self.name.clone().unwrap_or("Guard".to_string())
+    }
+}
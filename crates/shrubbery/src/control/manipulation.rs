@@ -1,9 +1,9 @@
 use ahash::HashMap;
 
-use super::{CTreeNode, CTreeNodeID, ControlNode, ControlTree};
+use super::{CTreeNode, CTreeNodeID, ControlNode, ControlTree, ROOT_ID};
 use crate::prelude::*;
 
-impl<D: Decorator + From<StandardDecorator>> ControlTree<D> {
+impl<D: Decorator + From<StandardDecorator>, C: Composite> ControlTree<D, C> {
     pub fn add_subtree_as_last_child(&mut self, from: CTreeNodeID, subtree: impl Into<Self>) {
         self.add_subtree_with_priority(from, usize::MAX, subtree)
     }
@@ -21,45 +21,70 @@ impl<D: Decorator + From<StandardDecorator>> ControlTree<D> {
         priority: usize,
         subtree: impl Into<Self>,
     ) {
-        //
-        let subtree_root =
-            self.add_floating_node(ControlNode::decorator(StandardDecorator::subtree()));
-
-        let siblings = self.tree.entry(from).or_default();
-        let index = priority.min(siblings.len());
-        siblings.insert(index, subtree_root);
+        self.graft_subtree(from, priority, subtree.into());
+    }
 
-        let ControlTree { nodes, tree } = subtree.into();
+    /// [`Self::add_subtree_with_priority`], returning the id of the
+    /// [`Subtree`](crate::control::decorators::Subtree) node it's added under & the new id of
+    /// every node in `subtree`.
+    ///
+    /// The root of `subtree` keeps running its children like the root does, under the subtree
+    /// node.
+    pub(crate) fn graft_subtree(
+        &mut self,
+        from: CTreeNodeID,
+        priority: usize,
+        subtree: Self,
+    ) -> (CTreeNodeID, HashMap<CTreeNodeID, CTreeNodeID>) {
+        let subtree_node = self.add_child_unchecked_with_priority(
+            from,
+            ControlNode::decorator(StandardDecorator::subtree()),
+            priority,
+        );
+
+        let ControlTree { nodes, tree } = subtree;
         let mut old_to_new = HashMap::default();
 
-        nodes.into_iter().filter(|n| !n.is_root()).for_each(|node| {
-            let old_id = node.id().unwrap();
-            let new_id = self.add_floating_node(node);
-            old_to_new.insert(old_id, new_id);
+        // leaves don't always have an entry of their own, so a node is in the tree if it's a
+        // parent or a child, anything else was removed
+        let in_tree = |id: &CTreeNodeID| {
+            tree.contains_key(id) || tree.values().any(|children| children.contains(id))
+        };
+        nodes
+            .into_iter()
+            .enumerate()
+            .map(|(index, node)| (CTreeNodeID::from(index), node))
+            .filter(|(old_id, _)| in_tree(old_id))
+            .for_each(|(old_id, node)| {
+                old_to_new.insert(old_id, self.add_floating_node(node));
+            });
+
+        tree.into_iter().for_each(|(old_parent, children)| {
+            // add new child ids to self
+            let new_children = children
+                .into_iter()
+                .flat_map(|old_child| old_to_new.get(&old_child).copied());
+
+            self.tree
+                .entry(old_to_new[&old_parent])
+                .or_default()
+                .extend(new_children);
         });
+        self.tree
+            .entry(subtree_node)
+            .or_default()
+            .push(old_to_new[&ROOT_ID]);
 
-        tree.into_iter()
-            .filter(|(p, _)| old_to_new.contains_key(p)) // skip the root
-            .for_each(|(old_parent, children)| {
-                // add new child ids to self
-                let new_children = children
-                    .into_iter()
-                    .flat_map(|old_child| old_to_new.get(&old_child));
-
-                self.tree
-                    .entry(old_parent)
-                    .or_default()
-                    .extend(new_children);
-            });
+        (subtree_node, old_to_new)
     }
 }
 
-impl<D: Decorator> ControlTree<D> {
+impl<D: Decorator, C: Composite> ControlTree<D, C> {
     /// Add a new node as a child with a priority (0 runs first).
     pub fn add_child(
         &mut self,
         parent_id: CTreeNodeID,
-        child: impl Into<CTreeNode<D>>,
+        child: impl Into<CTreeNode<D, C>>,
     ) -> ShrubberyResult<CTreeNodeID> {
         self.add_child_with_priority(parent_id, child, usize::MAX)
     }
@@ -68,17 +93,14 @@ impl<D: Decorator> ControlTree<D> {
     pub fn add_child_with_priority(
         &mut self,
         parent_id: CTreeNodeID,
-        child: impl Into<CTreeNode<D>>,
+        child: impl Into<CTreeNode<D, C>>,
         priority: usize,
     ) -> ShrubberyResult<CTreeNodeID> {
         let id = self.add_child_unchecked_with_priority(parent_id, child, priority);
 
         self.recurse_children_check_cycles(parent_id, vec![])
             .map(|_| id)
-            .map_err(|e| {
-                self.remove(id);
-                e
-            })
+            .inspect_err(|_| self.remove(id))
     }
 
     /// Adds a child node to the root of the tree.
@@ -90,7 +112,7 @@ impl<D: Decorator> ControlTree<D> {
     pub(crate) fn add_child_unchecked(
         &mut self,
         parent_id: CTreeNodeID,
-        child: impl Into<CTreeNode<D>>,
+        child: impl Into<CTreeNode<D, C>>,
     ) -> CTreeNodeID {
         self.add_child_unchecked_with_priority(parent_id, child, usize::MAX)
     }
@@ -103,7 +125,7 @@ impl<D: Decorator> ControlTree<D> {
     pub(crate) fn add_child_unchecked_with_priority(
         &mut self,
         parent_id: CTreeNodeID,
-        child: impl Into<CTreeNode<D>>,
+        child: impl Into<CTreeNode<D, C>>,
         priority: usize,
     ) -> CTreeNodeID {
         let mut child = child.into();
@@ -1,6 +1,6 @@
 use crate::{traits::*, ShrubberyError, ShrubberyResult};
 use ahash::HashMap;
-use control_nodes::ControlNode;
+use control_nodes::{ControlNode, Memory, StandardComposite};
 use decorators::StandardDecorator;
 use derive_more::From;
 
@@ -36,37 +36,37 @@ impl From<usize> for CTreeNodeID {
 }
 
 #[derive(Debug, Clone)]
-pub struct ControlTree<D: Decorator> {
-    pub(crate) nodes: Vec<CTreeNode<D>>,
+pub struct ControlTree<D: Decorator, C: Composite = StandardComposite> {
+    pub(crate) nodes: Vec<CTreeNode<D, C>>,
     pub(crate) tree: HashMap<CTreeNodeID, Vec<CTreeNodeID>>,
 }
 
 pub type StdControlTree = ControlTree<StandardDecorator>;
 
-impl<D: Decorator> Default for ControlTree<D> {
+impl<D: Decorator, C: Composite> Default for ControlTree<D, C> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<D: Decorator> std::ops::Index<CTreeNodeID> for ControlTree<D> {
-    type Output = CTreeNode<D>;
+impl<D: Decorator, C: Composite> std::ops::Index<CTreeNodeID> for ControlTree<D, C> {
+    type Output = CTreeNode<D, C>;
     fn index(&self, index: CTreeNodeID) -> &Self::Output {
         &self.nodes[index.0]
     }
 }
 
-impl<D: Decorator> std::ops::IndexMut<CTreeNodeID> for ControlTree<D> {
+impl<D: Decorator, C: Composite> std::ops::IndexMut<CTreeNodeID> for ControlTree<D, C> {
     fn index_mut(&mut self, index: CTreeNodeID) -> &mut Self::Output {
         &mut self.nodes[index.0]
     }
 }
 
-impl<D: Decorator> ControlTree<D> {
-    pub fn iter_control_nodes(&self) -> impl Iterator<Item = &ControlNode<D>> + '_ {
+impl<D: Decorator, C: Composite> ControlTree<D, C> {
+    pub fn iter_control_nodes(&self) -> impl Iterator<Item = &ControlNode<D, C>> + '_ {
         self.nodes.iter().filter_map(|n| n.try_as_control())
     }
-    pub fn iter_decorators(&self) -> impl Iterator<Item = &ControlNode<D>> + '_ {
+    pub fn iter_decorators(&self) -> impl Iterator<Item = &ControlNode<D, C>> + '_ {
         self.iter_control_nodes().filter(|c| c.is_decorator())
     }
     pub fn iter_tree(&self) -> impl Iterator<Item = (&CTreeNodeID, &Vec<CTreeNodeID>)> + '_ {
@@ -78,24 +78,61 @@ impl<D: Decorator> ControlTree<D> {
         self[ROOT_ID].status().unwrap_or_default()
     }
 
+    /// Run the control tree until the root node reaches a terminal [`Status`].
+    ///
+    /// This is built on [`Self::tick`], so leaves that return [`Status::Running`] are re-ticked
+    /// on the next traversal until they finish, and a tree that has already finished is
+    /// [`reset`](Self::reset) and run again from the start.
     pub fn run<Hook: ExecutorHook>(&mut self, hook: &mut Hook) -> Status {
-        while self.status() == Status::Running {
-            self.run_from(ROOT_ID, hook);
+        self.run_with_update_callback(hook, &mut NoCallback)
+    }
+
+    pub fn run_with_update_callback<Hook: ExecutorHook, Callback: UpdateCallback<D, C>>(
+        &mut self,
+        hook: &mut Hook,
+        cb: &mut Callback,
+    ) -> Status {
+        loop {
+            let status = self.tick_with_update_callback(hook, cb);
+            if status.is_terminal() {
+                return status;
+            }
         }
-        self.status()
     }
 
-    pub fn run_with_update_callback<Hook: ExecutorHook, Callback: UpdateCallback<D>>(
+    /// Advance the whole tree by exactly one traversal and return the status of the root.
+    ///
+    /// All per-node state is kept in the [`ControlTree`], so calling this repeatedly (e.g. once
+    /// per frame) continues where the previous traversal left off. If the previous traversal
+    /// finished the tree, it is [`reset`](Self::reset) first so the tree starts over.
+    pub fn tick<Hook: ExecutorHook>(&mut self, hook: &mut Hook) -> Status {
+        self.tick_with_update_callback(hook, &mut NoCallback)
+    }
+
+    pub fn tick_with_update_callback<Hook: ExecutorHook, Callback: UpdateCallback<D, C>>(
         &mut self,
         hook: &mut Hook,
         cb: &mut Callback,
     ) -> Status {
-        while self.status() == Status::Running {
-            self.run_from_with_update_callback(ROOT_ID, hook, cb);
+        if self.status().is_terminal() {
+            self.reset();
+        }
+        self.tick_from_with_update_callback(ROOT_ID, hook, cb)
+    }
+
+    /// Clear the status & state of every node in the tree (including decorator state such as
+    /// [`Repeater`](decorators::Repeater) retries) so it can be run again from the start.
+    ///
+    /// Leaves that are still [`Status::Running`] are reset without being halted, so a tree that is
+    /// mid-run should be [halted](Self::halt) from [`ROOT_ID`] first, like
+    /// [`ShrubberyBT::reset`](crate::bt::ShrubberyBT::reset) does.
+    pub fn reset(&mut self) {
+        for node in self.nodes.iter_mut() {
+            node.restart();
         }
-        self.status()
     }
 
+    /// Run the branch under `node_id` until it reaches a terminal [`Status`].
     pub fn run_from<Hook: ExecutorHook>(
         &mut self,
         node_id: CTreeNodeID,
@@ -104,67 +141,234 @@ impl<D: Decorator> ControlTree<D> {
         self.run_from_with_update_callback(node_id, hook, &mut NoCallback)
     }
 
-    pub fn run_from_with_update_callback<Hook: ExecutorHook, Callback: UpdateCallback<D>>(
+    pub fn run_from_with_update_callback<Hook: ExecutorHook, Callback: UpdateCallback<D, C>>(
         &mut self,
         node_id: CTreeNodeID,
         hook: &mut Hook,
         cb: &mut Callback,
     ) -> Status {
-        let mut node_status = self[node_id].tick();
-        cb.callback(self);
+        loop {
+            let node_status = self.tick_from_with_update_callback(node_id, hook, cb);
+            if node_status.is_terminal() {
+                return node_status;
+            }
+        }
+    }
 
-        while node_status.is_running() {
-            for child in self.children(&node_id) {
-                // tick the parent node & break if it's finished
+    /// Tick the branch under `node_id` once.
+    pub fn tick_from<Hook: ExecutorHook>(
+        &mut self,
+        node_id: CTreeNodeID,
+        hook: &mut Hook,
+    ) -> Status {
+        self.tick_from_with_update_callback(node_id, hook, &mut NoCallback)
+    }
 
-                if self[node_id].tick().is_terminal() {
-                    cb.callback(self);
+    /// Do a single traversal of the branch under `node_id`, visiting each child of a running
+    /// control node at most once. Children that return [`Status::Running`] are left running and
+    /// are picked up again by the next traversal.
+    pub fn tick_from_with_update_callback<Hook: ExecutorHook, Callback: UpdateCallback<D, C>>(
+        &mut self,
+        node_id: CTreeNodeID,
+        hook: &mut Hook,
+        cb: &mut Callback,
+    ) -> Status {
+        let status = match hook.pre_tick(node_id) {
+            PreTick::Tick => self.tick_children(node_id, hook, cb),
+            PreTick::Skip => self[node_id].tick(),
+            PreTick::Succeed => self.short_circuit(node_id, Status::Success, hook, cb),
+            PreTick::Fail => self.short_circuit(node_id, Status::Failure, hook, cb),
+        };
+        hook.post_tick(node_id, status);
 
-                    break;
-                }
-                if self[child].status().unwrap_or_default().is_success() {
-                    // don't re-run successful nodes
-                    continue;
-                }
+        status
+    }
 
-                if let CTreeNode::Leaf(leaf) = &self[child] {
-                    // hook the leaf node executor to get the status & update the control node with the
-                    // result
-                    let status = hook.hook(leaf);
-                    self[child].set_status(status); // update the leaf node status from the hook
-
-                    let update = ChildUpdate {
-                        status,
-                        child_id: child,
-                    };
-                    cb.callback(self);
-                    self[node_id].child_updated(update);
-                } else {
-                    // continue down the control tree, updating the control node with the eventual
-                    // result
-                    let status = self[child].tick();
-                    let subtree_status = match status {
-                        Status::Running => self.run_from_with_update_callback(child, hook, cb),
-                        _ => status,
-                    };
-                    let update = ChildUpdate {
-                        status: subtree_status,
-                        child_id: child,
-                    };
-                    self[node_id].child_updated(update);
-                }
-            }
-            // tell the node all the children have run.
-            self[node_id].all_children_seen();
+    /// The traversal of [`Self::tick_from_with_update_callback`], once the
+    /// [`ExecutorHook::pre_tick`] let it through.
+    fn tick_children<Hook: ExecutorHook, Callback: UpdateCallback<D, C>>(
+        &mut self,
+        node_id: CTreeNodeID,
+        hook: &mut Hook,
+        cb: &mut Callback,
+    ) -> Status {
+        let node_status = self[node_id].tick();
+        cb.callback(self);
 
-            node_status = self[node_id].tick();
-            self.handle_reset_requests(node_id);
-            cb.callback(self);
+        if !node_status.is_running() {
+            return node_status;
         }
+
+        let mut ticked = vec![];
+        let mut concurrent = self.hook_concurrent(node_id, hook);
+        for child in self.visit_order(node_id, hook, cb) {
+            // tick the parent node & break if it's finished
+            if self[node_id].tick().is_terminal() {
+                cb.callback(self);
+
+                break;
+            }
+            if self[node_id].yielded() {
+                break;
+            }
+            if !self.revisit(node_id, child) {
+                continue;
+            }
+            ticked.push(child);
+
+            if let CTreeNode::Leaf(leaf) = &self[child] {
+                // hook the leaf node executor to get the status & update the control node with the
+                // result
+                let status = match concurrent.remove(&child) {
+                    Some(status) => status,
+                    None => hook.hook(leaf),
+                };
+                self[child].set_status(status); // update the leaf node status from the hook
+                report_mutations(hook, cb);
+
+                let update = ChildUpdate {
+                    status,
+                    child_id: child,
+                };
+                cb.callback(self);
+                self[node_id].child_updated(update);
+            } else {
+                // continue down the control tree, updating the control node with the result of
+                // a single traversal of the subtree
+                let status = self[child].tick();
+                let subtree_status = match status {
+                    Status::Running => self.tick_from_with_update_callback(child, hook, cb),
+                    _ => status,
+                };
+                let update = ChildUpdate {
+                    status: subtree_status,
+                    child_id: child,
+                };
+                self[node_id].child_updated(update);
+            }
+        }
+        // leaves that ran concurrently after the node finished still need their status recorded,
+        // so the running ones get halted
+        for (child, status) in concurrent {
+            self[child].set_status(status);
+            ticked.push(child);
+        }
+        // tell the node all the children have run.
+        self[node_id].all_children_seen();
+
+        let node_status = self[node_id].tick();
+        self.handle_reset_requests(node_id, hook);
+        self.halt_preempted(node_id, &ticked, hook);
+        // halted leaves can change the blackboard too
+        report_mutations(hook, cb);
+        cb.callback(self);
+
+        node_status
+    }
+
+    /// Finish the decorator `node_id` without ticking its branch, by feeding it `status` as if it
+    /// came from its child. Anything still running underneath is halted.
+    fn short_circuit<Hook: ExecutorHook, Callback: UpdateCallback<D, C>>(
+        &mut self,
+        node_id: CTreeNodeID,
+        status: Status,
+        hook: &mut Hook,
+        cb: &mut Callback,
+    ) -> Status {
+        let child_id = self.tree[&node_id].first().copied().unwrap_or(node_id);
+        self[node_id].tick();
+        self[node_id].child_updated(ChildUpdate { status, child_id });
+        let node_status = self[node_id].tick();
+        self.halt_preempted(node_id, &[], hook);
+        report_mutations(hook, cb);
+        cb.callback(self);
+
         node_status
     }
 
-    fn handle_reset_requests(&mut self, node_id: CTreeNodeID) -> usize {
+    /// The children of `node_id` to visit in this traversal, see [`Control::visit_order`]. The
+    /// ones left out get halted by [`Self::halt_preempted`] since they aren't ticked.
+    fn visit_order<Hook: ExecutorHook, Callback: UpdateCallback<D, C>>(
+        &mut self,
+        node_id: CTreeNodeID,
+        hook: &mut Hook,
+        cb: &mut Callback,
+    ) -> Vec<CTreeNodeID> {
+        let children = self.children(&node_id);
+        let selected = hook.select(node_id, children.len());
+        if let Some(selection) = selected.as_ref().filter(|s| !s.scores.is_empty()) {
+            cb.scores(node_id, &selection.scores);
+        }
+        match self[node_id].visit_order(&children, selected) {
+            Some(order) => order
+                .into_iter()
+                .filter_map(|i| children.get(i).copied())
+                .collect(),
+            None => children,
+        }
+    }
+
+    /// Tick the leaf children of a node that [runs concurrently](Control::runs_concurrently) as
+    /// one batch through [`ExecutorHook::hook_concurrent`], returning their statuses to be fed to
+    /// the node in order.
+    ///
+    /// The batch is the leaves the traversal would tick, see [`Self::revisit`].
+    fn hook_concurrent<Hook: ExecutorHook>(
+        &mut self,
+        node_id: CTreeNodeID,
+        hook: &mut Hook,
+    ) -> HashMap<CTreeNodeID, Status> {
+        if !self[node_id].runs_concurrently() {
+            return Default::default();
+        }
+        let batch: Vec<CTreeNodeID> = self
+            .children(&node_id)
+            .into_iter()
+            .filter(|&child| self[child].try_as_leaf().is_some() && self.revisit(node_id, child))
+            .collect();
+        let leaves: Vec<&LeafNode> = batch
+            .iter()
+            .filter_map(|&child| self[child].try_as_leaf())
+            .collect();
+        let statuses = hook.hook_concurrent(&leaves);
+
+        batch.into_iter().zip(statuses).collect()
+    }
+
+    /// Whether `child` gets ticked again when the traversal reaches it under `node_id`, starting
+    /// its branch over first if the node (or its [`Memory`]) wants a fresh result instead of the
+    /// one it finished with last time.
+    fn revisit(&mut self, node_id: CTreeNodeID, child: CTreeNodeID) -> bool {
+        if self[node_id].skip(child) {
+            return false;
+        }
+        let status = self[child].status().unwrap_or_default();
+        if self[node_id].reevaluate(child) {
+            // start it over, whatever it finished with last time
+            if status.is_terminal() {
+                self.reset_branch(child);
+            }
+        } else if status.is_success() {
+            let conditional = self[child]
+                .try_as_leaf()
+                .is_some_and(|leaf| leaf.leaf_type == LeafType::Conditional);
+            match self[node_id].memory().unwrap_or_default() {
+                // don't re-run successful nodes
+                Memory::Remember => return false,
+                Memory::ReevaluateConditionsOnly if !conditional => return false,
+                Memory::ReevaluateConditionsOnly => {}
+                // start the branch over, so it doesn't just report it's status again
+                Memory::Reevaluate => self.reset_branch(child),
+            }
+        }
+        true
+    }
+
+    fn handle_reset_requests<Hook: ExecutorHook>(
+        &mut self,
+        node_id: CTreeNodeID,
+        hook: &mut Hook,
+    ) -> usize {
         if let Some(reset) = self[node_id]
             .try_as_control_mut()
             .map(|c| std::mem::take(&mut c.reset_requests))
@@ -172,7 +376,7 @@ impl<D: Decorator> ControlTree<D> {
             reset
                 .into_iter()
                 .map(|id| {
-                    self.reset_branch(id);
+                    self.halt(id, hook);
                 })
                 .count()
         } else {
@@ -180,6 +384,47 @@ impl<D: Decorator> ControlTree<D> {
         }
     }
 
+    /// Halt the children of `node_id` that are still [`Status::Running`] but have been
+    /// pre-empted, i.e. `node_id` finished without them or they weren't ticked by the last
+    /// traversal.
+    fn halt_preempted<Hook: ExecutorHook>(
+        &mut self,
+        node_id: CTreeNodeID,
+        ticked: &[CTreeNodeID],
+        hook: &mut Hook,
+    ) {
+        let finished = self[node_id].status().unwrap_or_default().is_terminal();
+        for child in self.children(&node_id) {
+            let running = self[child].status().is_some_and(|s| s.is_running());
+            if running && (finished || !ticked.contains(&child)) {
+                self.halt(child, hook);
+            }
+        }
+    }
+
+    /// Cancel the branch under `from`. Every [`Status::Running`] leaf in the branch is handed to
+    /// [`ExecutorHook::halt`] so it can stop whatever it started (and every running control node
+    /// to [`ExecutorHook::halt_control`]), then the branch is reset.
+    ///
+    /// If the parent of `from` is still running, the branch will be started again from scratch
+    /// the next time it is ticked.
+    pub fn halt<Hook: ExecutorHook>(&mut self, from: CTreeNodeID, hook: &mut Hook) {
+        let mut to_visit = vec![from];
+        while let Some(id) = to_visit.pop() {
+            match &self[id] {
+                CTreeNode::Leaf(leaf) if leaf.status.is_some_and(|s| s.is_running()) => {
+                    hook.halt(leaf);
+                }
+                node if node.status().is_some_and(|s| s.is_running()) => hook.halt_control(id),
+                _ => {}
+            }
+            self[id].reset();
+
+            to_visit.extend(self.tree[&id].iter().copied());
+        }
+    }
+
+    /// Reset the branch under `from` without halting any running leaves, see [`Self::halt`].
     pub fn reset_branch(&mut self, from: CTreeNodeID) {
         let mut to_visit = vec![from];
         while let Some(id) = to_visit.pop() {
@@ -206,9 +451,11 @@ impl<D: Decorator> ControlTree<D> {
     /// - No cycles
     /// - No dangling control nodes
     /// - Decorators have only one child
+    /// - Composites accept their children, see [`Composite::validate`]
     pub(crate) fn validate_bt_rules(&self) -> ShrubberyResult<()> {
         self.check_for_cycles()?;
         self.validate_decorators()?;
+        self.validate_composites()?;
         self.check_for_dangling_control()?;
         Ok(())
     }
@@ -217,11 +464,8 @@ impl<D: Decorator> ControlTree<D> {
     pub(crate) fn check_for_cycles(&self) -> ShrubberyResult<()> {
         if let Some(err) = self.iter_tree().find_map(|(&parent, children)| {
             children.iter().find_map(|&child| {
-                if let Err(e) = self.recurse_children_check_cycles(child, vec![parent]) {
-                    Some(e)
-                } else {
-                    None
-                }
+                self.recurse_children_check_cycles(child, vec![parent])
+                    .err()
             })
         }) {
             Err(err)
@@ -246,6 +490,23 @@ impl<D: Decorator> ControlTree<D> {
         }
     }
 
+    /// Composites decide which children they can work with
+    pub(crate) fn validate_composites(&self) -> ShrubberyResult<()> {
+        self.iter_control_nodes().try_for_each(|node| {
+            let (Some(id), Some(composite)) = (node.id, node.try_as_composite()) else {
+                return Ok(());
+            };
+            let children = self.children(&id);
+            composite
+                .validate(&children)
+                .map_err(|reason| ShrubberyError::InvalidComposite {
+                    composite: id,
+                    children,
+                    reason,
+                })
+        })
+    }
+
     /// Control nodes are by definition not leaf nodes so must have at least one child.
     pub(crate) fn check_for_dangling_control(&self) -> ShrubberyResult<()> {
         if let Some(dangling) = self
@@ -293,9 +554,9 @@ impl<D: Decorator> ControlTree<D> {
         old_to_new.insert(ROOT_ID, ROOT_ID);
         old_to_new.insert(old_id, new_id);
 
-        struct Deps<D: Decorator> {
+        struct Deps<D: Decorator, C: Composite> {
             old_to_new: HashMap<CTreeNodeID, CTreeNodeID>,
-            subtree: ControlTree<D>,
+            subtree: ControlTree<D, C>,
         }
 
         let mut deps = Deps {
@@ -317,10 +578,12 @@ impl<D: Decorator> ControlTree<D> {
         deps.subtree
     }
 
-    fn add_floating_node(&mut self, node: impl Into<CTreeNode<D>>) -> CTreeNodeID {
-        let node = node.into();
+    fn add_floating_node(&mut self, node: impl Into<CTreeNode<D, C>>) -> CTreeNodeID {
+        let mut node = node.into();
         let id = self.nodes.len().into();
+        node.set_id(id);
         self.nodes.push(node);
+        self.tree.entry(id).or_default();
         id
     }
 
@@ -328,7 +591,7 @@ impl<D: Decorator> ControlTree<D> {
         &self,
         from: CTreeNodeID,
         deps: &mut Deps,
-        f: impl Fn(&mut Deps, &CTreeNode<D>, &Vec<CTreeNodeID>),
+        f: impl Fn(&mut Deps, &CTreeNode<D, C>, &Vec<CTreeNodeID>),
     ) {
         let mut to_visit = vec![from];
         while let Some(from) = to_visit.pop() {
@@ -376,7 +639,7 @@ impl<D: Decorator> ControlTree<D> {
         &mut self,
         parent_id: CTreeNodeID,
         move_down: &[CTreeNodeID],
-        node: impl Into<CTreeNode<D>>,
+        node: impl Into<CTreeNode<D, C>>,
     ) -> CTreeNodeID {
         let node = node.into();
         let mut i = 0;
@@ -412,15 +675,15 @@ impl<D: Decorator> ControlTree<D> {
     pub fn iter_children_mut<'a, O>(
         &'a mut self,
         node_id: &CTreeNodeID,
-        mut f: impl FnMut(&mut CTreeNode<D>) -> O + 'a,
-    ) -> impl Iterator<Item = O> + '_ {
+        mut f: impl FnMut(&mut CTreeNode<D, C>) -> O + 'a,
+    ) -> impl Iterator<Item = O> + 'a {
         self.tree[node_id]
             .clone()
             .into_iter()
             .map(move |id| f(self.node_mut(id)))
     }
 
-    pub fn node_mut(&mut self, id: CTreeNodeID) -> &mut CTreeNode<D> {
+    pub fn node_mut(&mut self, id: CTreeNodeID) -> &mut CTreeNode<D, C> {
         &mut self.nodes[id.0]
     }
 
@@ -428,7 +691,10 @@ impl<D: Decorator> ControlTree<D> {
         self.tree[node_id].clone()
     }
 
-    pub fn iter_children(&self, node_id: &CTreeNodeID) -> impl Iterator<Item = &CTreeNode<D>> + '_ {
+    pub fn iter_children(
+        &self,
+        node_id: &CTreeNodeID,
+    ) -> impl Iterator<Item = &CTreeNode<D, C>> + '_ {
         self.tree[node_id].iter().map(|&id| &self[id])
     }
 
@@ -437,14 +703,27 @@ impl<D: Decorator> ControlTree<D> {
     }
 }
 
+/// Hand the changes `hook` recorded since it was last asked to `cb`, see
+/// [`ExecutorHook::mutations`]
+fn report_mutations<D: Decorator, C: Composite>(
+    hook: &mut impl ExecutorHook,
+    cb: &mut impl UpdateCallback<D, C>,
+) {
+    for mutation in hook.mutations() {
+        cb.mutation(&mutation);
+    }
+}
+
+// there is only ever one root, so it's not worth boxing
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq, Eq, From)]
-pub enum CTreeNode<D: Decorator> {
+pub enum CTreeNode<D: Decorator, C: Composite = StandardComposite> {
     Root(RootNode),
-    Control(ControlNode<D>),
+    Control(ControlNode<D, C>),
     Leaf(LeafNode),
 }
 
-impl<D: Decorator> CTreeNode<D> {
+impl<D: Decorator, C: Composite> CTreeNode<D, C> {
     pub fn try_as_leaf_mut(&mut self) -> Option<&mut LeafNode> {
         match self {
             CTreeNode::Leaf(c) => Some(c),
@@ -460,14 +739,14 @@ impl<D: Decorator> CTreeNode<D> {
     pub fn is_leaf(&self) -> bool {
         self.try_as_leaf().is_some()
     }
-    pub fn try_as_control_mut(&mut self) -> Option<&mut ControlNode<D>> {
+    pub fn try_as_control_mut(&mut self) -> Option<&mut ControlNode<D, C>> {
         match self {
             CTreeNode::Control(c) => Some(c),
             _ => None,
         }
     }
 
-    pub fn try_as_control(&self) -> Option<&ControlNode<D>> {
+    pub fn try_as_control(&self) -> Option<&ControlNode<D, C>> {
         match &self {
             CTreeNode::Control(c) => Some(c),
             _ => None,
@@ -488,6 +767,23 @@ impl<D: Decorator> CTreeNode<D> {
         self.try_as_root().is_some()
     }
 
+    /// The [`Memory`] of a control node, `None` for leaves
+    pub fn memory(&self) -> Option<Memory> {
+        match self {
+            CTreeNode::Root(root) => Some(root.0.memory),
+            CTreeNode::Control(control) => Some(control.memory),
+            CTreeNode::Leaf(_) => None,
+        }
+    }
+    /// Set the [`Memory`] of a control node, leaves have none so they are left alone
+    pub fn set_memory(&mut self, memory: Memory) {
+        match self {
+            CTreeNode::Root(root) => root.0.memory = memory,
+            CTreeNode::Control(control) => control.memory = memory,
+            CTreeNode::Leaf(_) => {}
+        }
+    }
+
     pub fn root() -> Self {
         CTreeNode::Root(RootNode(ControlNode::sequence()))
     }
@@ -519,12 +815,22 @@ impl<D: Decorator> CTreeNode<D> {
         }
     }
     pub fn reset(&mut self) {
-        self.clear_status();
+        // the node resets before its status is cleared, so decorators can tell they were halted
         match self {
             CTreeNode::Root(root) => root.0.reset(),
             CTreeNode::Control(control) => control.reset(),
             CTreeNode::Leaf(leaf) => leaf.reset(),
         }
+        self.clear_status();
+    }
+    /// [`Self::reset`] as part of resetting the whole tree, see [`Decorator::restart`].
+    pub fn restart(&mut self) {
+        match self {
+            CTreeNode::Root(root) => root.0.restart(),
+            CTreeNode::Control(control) => control.restart(),
+            CTreeNode::Leaf(leaf) => leaf.reset(),
+        }
+        self.clear_status();
     }
     pub fn clear_status(&mut self) {
         match self {
@@ -549,7 +855,7 @@ impl<D: Decorator> CTreeNode<D> {
     }
 }
 
-impl<D: Decorator> Control for CTreeNode<D> {
+impl<D: Decorator, C: Composite> Control for CTreeNode<D, C> {
     fn tick(&mut self) -> Status {
         let status = match self {
             CTreeNode::Root(root) => root.tick(),
@@ -574,6 +880,58 @@ impl<D: Decorator> Control for CTreeNode<D> {
             CTreeNode::Leaf(l) => l.all_children_seen(),
         }
     }
+
+    fn yielded(&self) -> bool {
+        match self {
+            CTreeNode::Root(r) => r.yielded(),
+            CTreeNode::Control(c) => c.yielded(),
+            CTreeNode::Leaf(l) => l.yielded(),
+        }
+    }
+
+    fn reevaluate_conditions(&self) -> bool {
+        match self {
+            CTreeNode::Root(r) => r.reevaluate_conditions(),
+            CTreeNode::Control(c) => c.reevaluate_conditions(),
+            CTreeNode::Leaf(l) => l.reevaluate_conditions(),
+        }
+    }
+
+    fn runs_concurrently(&self) -> bool {
+        match self {
+            CTreeNode::Root(r) => r.runs_concurrently(),
+            CTreeNode::Control(c) => c.runs_concurrently(),
+            CTreeNode::Leaf(l) => l.runs_concurrently(),
+        }
+    }
+
+    fn visit_order(
+        &mut self,
+        children: &[CTreeNodeID],
+        selected: Option<Selection>,
+    ) -> Option<Vec<usize>> {
+        match self {
+            CTreeNode::Root(r) => r.visit_order(children, selected),
+            CTreeNode::Control(c) => c.visit_order(children, selected),
+            CTreeNode::Leaf(l) => l.visit_order(children, selected),
+        }
+    }
+
+    fn skip(&self, child: CTreeNodeID) -> bool {
+        match self {
+            CTreeNode::Root(r) => r.skip(child),
+            CTreeNode::Control(c) => c.skip(child),
+            CTreeNode::Leaf(l) => l.skip(child),
+        }
+    }
+
+    fn reevaluate(&self, child: CTreeNodeID) -> bool {
+        match self {
+            CTreeNode::Root(r) => r.reevaluate(child),
+            CTreeNode::Control(c) => c.reevaluate(child),
+            CTreeNode::Leaf(l) => l.reevaluate(child),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
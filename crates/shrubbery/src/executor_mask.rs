@@ -1,13 +1,17 @@
 //! # Connects [`LeafNode(s)`](crate::control::LeafNode) to [`Executor`] & [`Conditional`]
 //! implementations.
 
+use std::fmt::Debug;
+
 use ahash::HashMap;
 use derive_more::From;
 
 use crate::{
-    control::{CTreeNodeID, LeafNode},
+    bt::lens::{Lens, Lensed},
+    control::{CTreeNodeID, ControlTree, LeafNode, ROOT_ID},
+    kv_blackboard::{Port, Ports},
     traits::*,
-    Status,
+    ShrubberyError, ShrubberyResult, Status,
 };
 
 #[derive(Debug, Clone, Copy, From)]
@@ -27,10 +31,19 @@ struct ConditionalID(usize);
 ///
 /// Importantly, this type implements [`ExecutorHook`], so it can plug into
 pub struct TaskHook<'a, H: ActionHandler> {
-    pub dispatch: &'a LeafDispatch<H>,
+    pub dispatch: &'a mut LeafDispatch<H>,
     pub blackboard: &'a mut H::Bb,
 }
 
+impl<'a, H: ActionHandler> TaskHook<'a, H> {
+    pub fn new(dispatch: &'a mut LeafDispatch<H>, blackboard: &'a mut H::Bb) -> Self {
+        Self {
+            dispatch,
+            blackboard,
+        }
+    }
+}
+
 impl<H: ActionHandler> ExecutorHook for TaskHook<'_, H> {
     fn hook(&mut self, leaf: &LeafNode) -> Status {
         let TaskHook {
@@ -47,12 +60,146 @@ impl<H: ActionHandler> ExecutorHook for TaskHook<'_, H> {
         };
 
         match *target_id {
-            TaskID::Executor(e) => leaf_mask[e].execute(blackboard),
-            TaskID::Conditional(c) => leaf_mask[c].conditional(blackboard),
+            TaskID::Executor(e) => tick_executor(&mut leaf_mask[e], leaf, blackboard),
+            TaskID::Conditional(c) => {
+                let conditional = &leaf_mask[c];
+                tick_conditional(
+                    conditional,
+                    || conditional_name(leaf, conditional),
+                    blackboard,
+                    leaf_mask.read_only_check,
+                )
+            }
+        }
+    }
+
+    fn halt(&mut self, leaf: &LeafNode) {
+        let Some(target_id) = leaf.id.as_ref().and_then(|id| self.dispatch.mask.get(id)) else {
+            return;
+        };
+        // conditionals are read-only & never left running, so there is nothing to stop
+        if let TaskID::Executor(e) = *target_id {
+            self.dispatch[e].on_halted(self.blackboard);
+        }
+    }
+
+    fn pre_tick(&mut self, node_id: CTreeNodeID) -> PreTick {
+        let read_only_check = self.dispatch.read_only_check;
+        match self.dispatch.decorators.get_mut(&node_id) {
+            Some(decorator) => {
+                decorator.before_tick(self.blackboard);
+                let name = decorator.name().unwrap_or_else(|| format!("{decorator:?}"));
+                // `pre_tick` only gets `&BB` too, see `Precondition`
+                read_only(
+                    read_only_check,
+                    self.blackboard,
+                    || format!("Blackboard decorator {name} (node {node_id:?})"),
+                    |blackboard| decorator.pre_tick(blackboard),
+                )
+            }
+            None => PreTick::Tick,
+        }
+    }
+
+    fn post_tick(&mut self, node_id: CTreeNodeID, status: Status) {
+        if let Some(decorator) = self.dispatch.decorators.get_mut(&node_id) {
+            decorator.post_tick(self.blackboard, status);
+        }
+    }
+
+    fn halt_control(&mut self, node_id: CTreeNodeID) {
+        if let Some(decorator) = self.dispatch.decorators.get_mut(&node_id) {
+            decorator.halt(self.blackboard);
         }
     }
+
+    fn select(&mut self, node_id: CTreeNodeID, children: usize) -> Option<Selection> {
+        self.dispatch
+            .selectors
+            .get_mut(&node_id)
+            .map(|selector| selector.select(self.blackboard, children))
+    }
+}
+
+/// Drive an [`Executor`] through its lifecycle for a single tick of `leaf`.
+pub(crate) fn tick_executor<BB: Blackboard, E: Executor<BB>>(
+    executor: &mut E,
+    leaf: &LeafNode,
+    blackboard: &mut BB,
+) -> Status {
+    let status = if leaf.status.is_some_and(|s| s.is_running()) {
+        executor.on_running(blackboard)
+    } else {
+        executor.on_start(blackboard)
+    };
+    if status.is_terminal() {
+        executor.on_end(blackboard, status);
+    }
+    status
+}
+
+/// Tick `conditional`, called `name()` in the logs. Debug builds warn if it returns
+/// [`Status::Running`], and report it if it changed the blackboard when `read_only_check` is set,
+/// see [`LeafDispatch::check_read_only`].
+pub(crate) fn tick_conditional<BB: Blackboard, C: Conditional<BB>>(
+    conditional: &C,
+    name: impl Fn() -> String,
+    blackboard: &BB,
+    read_only_check: Option<BlackboardEq<BB>>,
+) -> Status {
+    if !cfg!(debug_assertions) {
+        return conditional.conditional(blackboard);
+    }
+    let status = read_only(read_only_check, blackboard, &name, |blackboard| {
+        conditional.conditional(blackboard)
+    });
+    if status.is_running() {
+        log::warn!(
+            "{} returned Status::Running, conditionals should decide straight away",
+            name()
+        );
+    }
+    status
+}
+
+/// Call `read` with the blackboard. Debug builds log an error if it changed the blackboard
+/// anyway when `read_only_check` is set, interior mutability (`Cell`, `RefCell`, `Mutex`...) gets
+/// around the `&BB`.
+fn read_only<BB: Blackboard, T>(
+    read_only_check: Option<BlackboardEq<BB>>,
+    blackboard: &BB,
+    name: impl Fn() -> String,
+    read: impl FnOnce(&BB) -> T,
+) -> T {
+    let Some(eq) = read_only_check.filter(|_| cfg!(debug_assertions)) else {
+        return read(blackboard);
+    };
+    let before = blackboard.clone();
+    let output = read(blackboard);
+    if !eq(&before, blackboard) {
+        log::error!("{} changed the blackboard, it must be read-only", name());
+    }
+    output
+}
+
+/// Name of a conditional leaf in the logs
+pub(crate) fn conditional_name(leaf: &LeafNode, conditional: &impl Debug) -> String {
+    let name = leaf
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("{conditional:?}"));
+    format!("Conditional {name} (leaf {:?})", leaf.id)
+}
+
+/// Borrowed [`Executor`] or [`Conditional`] of a single leaf, see [`LeafDispatch::tasks_mut`].
+pub(crate) enum TaskMut<'a, H: ActionHandler> {
+    Executor(&'a mut H::Execute),
+    Conditional(&'a H::Condition),
 }
 
+/// `PartialEq::eq` of a blackboard, see [`LeafDispatch::check_read_only`]
+pub(crate) type BlackboardEq<BB> = fn(&BB, &BB) -> bool;
+
 /// Dispatch to [`Conditional`]/[`Executor`] implementers when  [`LeafNode`] is ticked.
 #[derive(Debug, Clone)]
 pub struct LeafDispatch<Handler: ActionHandler> {
@@ -62,6 +209,14 @@ pub struct LeafDispatch<Handler: ActionHandler> {
     executors: Vec<Handler::Execute>,
     /// Maps which leaf node corresponds to which [`Executor`]/[`Conditional`]
     mask: HashMap<CTreeNodeID, TaskID>,
+    /// [`BlackboardDecorator`]s, keyed by the decorator node they are attached to
+    decorators: HashMap<CTreeNodeID, Box<dyn BlackboardDecorator<Handler::Bb>>>,
+    /// [`BlackboardSelector`]s, keyed by the composite node they are attached to
+    selectors: HashMap<CTreeNodeID, Box<dyn BlackboardSelector<Handler::Bb>>>,
+    /// Keys the caller sets on the blackboard before the tree runs, see [`Ports`]
+    presets: Vec<Port>,
+    /// Compares the blackboard before & after each [`Conditional`], see [`Self::check_read_only`]
+    read_only_check: Option<BlackboardEq<Handler::Bb>>,
 }
 
 impl<H: ActionHandler> Default for LeafDispatch<H> {
@@ -70,6 +225,10 @@ impl<H: ActionHandler> Default for LeafDispatch<H> {
             conditionals: Default::default(),
             executors: Default::default(),
             mask: Default::default(),
+            decorators: Default::default(),
+            selectors: Default::default(),
+            presets: Default::default(),
+            read_only_check: None,
         }
     }
 }
@@ -88,6 +247,203 @@ impl<H: ActionHandler> LeafDispatch<H> {
         self.conditionals.push(conditional);
         self.mask.insert(id, target_id);
     }
+
+    /// Attach a [`BlackboardDecorator`] to a particular (decorator) node
+    pub fn add_blackboard_decorator(
+        &mut self,
+        id: CTreeNodeID,
+        decorator: impl BlackboardDecorator<H::Bb> + 'static,
+    ) {
+        self.decorators.insert(id, Box::new(decorator));
+    }
+
+    /// The [`BlackboardDecorator`] attached to a node
+    pub fn blackboard_decorator(
+        &self,
+        id: &CTreeNodeID,
+    ) -> Option<&dyn BlackboardDecorator<H::Bb>> {
+        self.decorators.get(id).map(|d| d.as_ref())
+    }
+
+    /// Attach a [`BlackboardSelector`] to a particular (composite) node
+    pub fn add_blackboard_selector(
+        &mut self,
+        id: CTreeNodeID,
+        selector: impl BlackboardSelector<H::Bb> + 'static,
+    ) {
+        self.selectors.insert(id, Box::new(selector));
+    }
+
+    /// The [`BlackboardSelector`] attached to a node
+    pub fn blackboard_selector(&self, id: &CTreeNodeID) -> Option<&dyn BlackboardSelector<H::Bb>> {
+        self.selectors.get(id).map(|s| s.as_ref())
+    }
+
+    /// The [`Executor`] assigned to a leaf node
+    pub fn executor(&self, id: &CTreeNodeID) -> Option<&H::Execute> {
+        match self.mask.get(id)? {
+            TaskID::Executor(e) => Some(&self[*e]),
+            TaskID::Conditional(_) => None,
+        }
+    }
+
+    /// Mutable access to the [`Executor`] assigned to a leaf node
+    pub fn executor_mut(&mut self, id: &CTreeNodeID) -> Option<&mut H::Execute> {
+        match *self.mask.get(id)? {
+            TaskID::Executor(e) => Some(&mut self[e]),
+            TaskID::Conditional(_) => None,
+        }
+    }
+
+    /// See [`Self::check_read_only`]
+    pub(crate) fn read_only_check(&self) -> Option<BlackboardEq<H::Bb>> {
+        self.read_only_check
+    }
+
+    /// Borrow the tasks of several leaves at once, so they can be run at the same time. `None` for
+    /// leaves that aren't handled by this dispatch, or that are repeated.
+    pub(crate) fn tasks_mut(&mut self, leaves: &[&LeafNode]) -> Vec<Option<TaskMut<'_, H>>> {
+        let LeafDispatch {
+            conditionals,
+            executors,
+            mask,
+            ..
+        } = self;
+        let mut executors: Vec<_> = executors.iter_mut().map(Some).collect();
+
+        leaves
+            .iter()
+            .map(|leaf| match *mask.get(leaf.id.as_ref()?)? {
+                TaskID::Executor(e) => executors[e.0].take().map(TaskMut::Executor),
+                TaskID::Conditional(c) => Some(TaskMut::Conditional(&conditionals[c.0])),
+            })
+            .collect()
+    }
+
+    /// The [`Conditional`] assigned to a leaf node
+    pub fn conditional(&self, id: &CTreeNodeID) -> Option<&H::Condition> {
+        match self.mask.get(id)? {
+            TaskID::Conditional(c) => Some(&self[*c]),
+            TaskID::Executor(_) => None,
+        }
+    }
+
+    /// The [`Ports`] declared by the task of a leaf node
+    pub fn ports(&self, id: &CTreeNodeID) -> Ports {
+        match self.mask.get(id) {
+            Some(TaskID::Executor(e)) => self[*e].ports(),
+            Some(TaskID::Conditional(c)) => self[*c].ports(),
+            None => Ports::default(),
+        }
+    }
+
+    /// Take over the tasks, decorators, selectors & presets of another dispatch, run through
+    /// `lens`. The leaves they are assigned to get their ids from `new_ids`, see
+    /// [`BTLayer::mount_subtree`](crate::bt::builder::BTLayer::mount_subtree).
+    ///
+    /// Whether conditionals are [checked to be read-only](Self::check_read_only) is up to this
+    /// dispatch, the check compares the whole blackboard so it covers the mounted ones too.
+    pub fn mount<Sub: ActionHandler>(
+        &mut self,
+        sub: LeafDispatch<Sub>,
+        lens: Lens<H::Bb, Sub::Bb>,
+        new_ids: &HashMap<CTreeNodeID, CTreeNodeID>,
+    ) where
+        H::Bb: 'static,
+        Sub::Bb: 'static,
+        H::Execute: From<Lensed<H::Bb, Sub::Bb, Sub::Execute>>,
+        H::Condition: From<Lensed<H::Bb, Sub::Bb, Sub::Condition>>,
+    {
+        for (old_id, task) in &sub.mask {
+            let Some(&id) = new_ids.get(old_id) else {
+                continue;
+            };
+            match *task {
+                TaskID::Executor(e) => {
+                    self.add_executor(id, Lensed::new(lens, sub[e].clone()).into());
+                }
+                TaskID::Conditional(c) => {
+                    self.add_conditional(id, Lensed::new(lens, sub[c].clone()).into());
+                }
+            }
+        }
+        for (old_id, decorator) in sub.decorators {
+            if let Some(&id) = new_ids.get(&old_id) {
+                self.add_blackboard_decorator(id, Lensed::new(lens, decorator));
+            }
+        }
+        for (old_id, selector) in sub.selectors {
+            if let Some(&id) = new_ids.get(&old_id) {
+                self.add_blackboard_selector(id, Lensed::new(lens, selector));
+            }
+        }
+        self.presets.extend(sub.presets);
+        if sub.read_only_check.is_some() && self.read_only_check.is_none() {
+            log::warn!(
+                "The mounted subtree checks its conditionals are read-only, call \
+                 `check_read_only` on the tree it's mounted in to keep checking them"
+            );
+        }
+    }
+
+    /// In debug builds, log an error if a [`Conditional`] (or [`BlackboardDecorator::pre_tick`])
+    /// changes the blackboard when it's ticked through a [`TaskHook`], which it can through
+    /// interior mutability despite only getting a `&BB`.
+    ///
+    /// Every conditional clones the blackboard to compare against, so this is opt-in.
+    pub fn check_read_only(&mut self)
+    where
+        H::Bb: PartialEq,
+    {
+        self.read_only_check = Some(PartialEq::eq);
+    }
+
+    /// [`BlackboardDecorator::halt`] every decorator, so none of them hold on to state from before
+    /// the tree was reset
+    pub fn halt_decorators(&mut self, blackboard: &mut H::Bb) {
+        for decorator in self.decorators.values_mut() {
+            decorator.halt(blackboard);
+        }
+    }
+
+    /// Mark `port` as set by the caller before the tree runs
+    pub fn add_preset(&mut self, port: Port) {
+        self.presets.push(port);
+    }
+
+    /// Check every input port is an output of a leaf that comes before it in `control_tree`
+    /// (depth first, in the order the children were added), or was preset, with the same type.
+    pub fn check_ports<D: Decorator, C: Composite>(
+        &self,
+        control_tree: &ControlTree<D, C>,
+    ) -> ShrubberyResult<()> {
+        let mut available: HashMap<&'static str, Port> =
+            self.presets.iter().map(|port| (port.name, *port)).collect();
+        let mut to_visit = vec![ROOT_ID];
+        while let Some(id) = to_visit.pop() {
+            to_visit.extend(control_tree.children(&id).into_iter().rev());
+            if !control_tree[id].is_leaf() {
+                continue;
+            }
+            let ports = self.ports(&id);
+            for input in ports.inputs {
+                let reason = match available.get(input.name) {
+                    None => "not written by an earlier leaf or preset".to_string(),
+                    Some(port) if port.type_id != input.type_id => {
+                        format!("read as {}, written as {}", input.type_name, port.type_name)
+                    }
+                    Some(_) => continue,
+                };
+                return Err(ShrubberyError::InvalidPort {
+                    leaf: id,
+                    port: input,
+                    reason,
+                });
+            }
+            available.extend(ports.outputs.into_iter().map(|port| (port.name, port)));
+        }
+        Ok(())
+    }
 }
 
 /* --- Boilerplate --- */
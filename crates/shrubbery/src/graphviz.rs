@@ -10,6 +10,9 @@ use graphviz_rust::printer::PrinterContext;
 
 use crate::control::control_nodes::ControlNode;
 use crate::control::control_nodes::ControlNodeType;
+use crate::control::control_nodes::Memory;
+use crate::control::control_nodes::ParallelPolicy;
+use crate::control::control_nodes::StandardComposite;
 use crate::control::CTreeNode;
 use crate::control::CTreeNodeID;
 use crate::control::ControlTree;
@@ -17,6 +20,7 @@ use crate::control::LeafNode;
 use crate::control::RootNode;
 use crate::control::ROOT_ID;
 use crate::prelude::StandardDecorator;
+use crate::traits::Composite;
 use crate::traits::Decorator;
 use crate::traits::ExecutorHook;
 use crate::traits::UpdateCallback;
@@ -25,10 +29,30 @@ use crate::Status;
 pub const SEQUENCE_SYMBOL: &str = "➡";
 pub const FALLBACK_SYMBOL: &str = "?";
 pub const PARALLEL_SYMBOL: &str = "⇉";
+pub const REACTIVE_SEQUENCE_SYMBOL: &str = "R➡";
+pub const REACTIVE_FALLBACK_SYMBOL: &str = "R?";
 pub const LOOP_SYMBOL: &str = "↺";
 pub const DECORATOR_SYMBOL: &str = "δ";
 pub const INVERT_SYMBOL: &str = "!";
 pub const SUBTREE_SYMBOL: &str = "🌳";
+pub const TIMEOUT_SYMBOL: &str = "⏱";
+pub const BACKOFF_SYMBOL: &str = "↺⏳";
+pub const COOLDOWN_SYMBOL: &str = "❄";
+pub const RETRY_UNTIL_SUCCESS_SYMBOL: &str = "↺✗";
+pub const KEEP_RUNNING_SYMBOL: &str = "↺✓";
+pub const INFINITE_SYMBOL: &str = "∞";
+pub const FORCE_SUCCESS_SYMBOL: &str = "✓!";
+pub const FORCE_FAILURE_SYMBOL: &str = "✗!";
+pub const RUN_ONCE_SYMBOL: &str = "1×";
+pub const GUARD_SYMBOL: &str = "🛡";
+pub const SWITCH_SYMBOL: &str = "⑂";
+pub const RANDOM_SYMBOL: &str = "?🎲";
+pub const WEIGHTED_RANDOM_SYMBOL: &str = "🎲";
+pub const UTILITY_SYMBOL: &str = "⚖";
+pub const REEVALUATE_SYMBOL: &str = "⟳";
+pub const REEVALUATE_CONDITIONS_SYMBOL: &str = "⟳◯";
+pub const IF_THEN_ELSE_SYMBOL: &str = "◇";
+pub const WHILE_DO_ELSE_SYMBOL: &str = "↻◇";
 
 const INACTIVE_COLOR: &str = "gray";
 
@@ -102,11 +126,10 @@ impl GraphvizAnimator {
         // html
         buf.push_str("<body>\n");
         buf.push_str("<svg width=\"100%\" height=\"100%\">");
+        // graphviz outputs a <svg> for each frame, we don't want that, just the inner stuff
+        let svg_tag = regex::Regex::new(r"<[/]?svg[^>]*>").unwrap();
         for frame in frames {
-            // graphviz outputs a <svg> for each frame, we don't want that, just the inner stuff
-            let strip_svg = regex::Regex::new(r"<[/]?svg[^>]*>")
-                .unwrap()
-                .replace_all(&frame, "");
+            let strip_svg = svg_tag.replace_all(&frame, "");
             buf.push_str(&strip_svg);
         }
         buf.push_str("</svg>\n");
@@ -168,14 +191,16 @@ impl GraphvizAnimator {
     }
 }
 
-impl<D: Decorator + GraphvizAttrs> UpdateCallback<D> for GraphvizAnimator {
-    fn callback(&mut self, state: &ControlTree<D>) {
+impl<D: Decorator + GraphvizAttrs, C: Composite + GraphvizAttrs> UpdateCallback<D, C>
+    for GraphvizAnimator
+{
+    fn callback(&mut self, state: &ControlTree<D, C>) {
         let graph = state.graphviz_graph();
         self.add_frame(graph);
     }
 }
 
-impl<D: Decorator + GraphvizAttrs> ControlTree<D> {
+impl<D: Decorator + GraphvizAttrs, C: Composite + GraphvizAttrs> ControlTree<D, C> {
     /// Runs the control tree and saves the animation to `out/[name].html
     ///
     /// XXX: This writes a new svg for every frame, kinda scuffed & not good for performance so
@@ -280,7 +305,7 @@ impl<D: Decorator + GraphvizAttrs> ControlTree<D> {
     }
 }
 
-impl<D: Decorator + GraphvizAttrs> GraphvizNode for CTreeNode<D> {
+impl<D: Decorator + GraphvizAttrs, C: Composite + GraphvizAttrs> GraphvizNode for CTreeNode<D, C> {
     fn graphviz_node(&self) -> Node {
         match self {
             CTreeNode::Root(root) => root.graphviz_node(),
@@ -292,7 +317,12 @@ impl<D: Decorator + GraphvizAttrs> GraphvizNode for CTreeNode<D> {
 
 impl GraphvizNode for RootNode {
     fn graphviz_node(&self) -> Node {
-        node!("root", self.graphviz_attrs())
+        // a mounted subtree keeps its root, under its own id
+        let id = match self.0.id {
+            Some(id) => format!("\"Root{}\"", id.index()),
+            None => "root".to_string(),
+        };
+        node!(id, self.graphviz_attrs())
     }
 }
 
@@ -305,8 +335,13 @@ impl GraphvizAttrs for RootNode {
         attrs.push(attr!("shape", "circle"));
         attrs.push(attr!("penwidth", "2.0"));
 
-        let status_tip = format!("\"{}\"", status_str(self.0.status));
+        let status_tip = format!(
+            "\"{}\\nmemory: {:?}\"",
+            status_str(self.0.status),
+            self.0.memory
+        );
         attrs.push(attr!("tooltip", status_tip));
+        attrs.extend(self.0.memory.graphviz_attrs());
 
         attrs.extend(self.0.status.graphviz_attrs());
         attrs
@@ -383,7 +418,9 @@ fn status_str(status: Option<Status>) -> &'static str {
 }
 
 /* --- ControlNode --- */
-impl<D: Decorator + GraphvizAttrs> GraphvizNode for ControlNode<D> {
+impl<D: Decorator + GraphvizAttrs, C: Composite + GraphvizAttrs> GraphvizNode
+    for ControlNode<D, C>
+{
     fn graphviz_node(&self) -> Node {
         let id = self.id.unwrap().graphviz_id();
 
@@ -391,7 +428,9 @@ impl<D: Decorator + GraphvizAttrs> GraphvizNode for ControlNode<D> {
     }
 }
 
-impl<D: Decorator + GraphvizAttrs> GraphvizAttrs for ControlNode<D> {
+impl<D: Decorator + GraphvizAttrs, C: Composite + GraphvizAttrs> GraphvizAttrs
+    for ControlNode<D, C>
+{
     fn graphviz_attrs(&self) -> Vec<Attribute> {
         let mut attrs = self.common_attrs();
         attrs.extend(self.node_type.graphviz_attrs());
@@ -399,13 +438,14 @@ impl<D: Decorator + GraphvizAttrs> GraphvizAttrs for ControlNode<D> {
     }
 }
 
-impl<D: Decorator> ControlNode<D> {
+impl<D: Decorator, C: Composite> ControlNode<D, C> {
     pub fn common_attrs(&self) -> Vec<Attribute> {
         let mut attrs = vec![];
 
         attrs.push(attr!("penwidth", "2.0"));
 
         let status_tip = status_str(self.status);
+        let memory = format!("\\nmemory: {:?}", self.memory);
 
         // let shape = match &self.node_type {
         //     ControlNodeType::Decorator(_) => {
@@ -420,9 +460,10 @@ impl<D: Decorator> ControlNode<D> {
         attrs.push(attr!("shape", shape));
 
         let tip = match &self.node_type {
-            ControlNodeType::Sequence(_) => format!("\"Sequence ({status_tip})\""),
-            ControlNodeType::Fallback(_) => format!("\"Fallback ({status_tip})\""),
-            ControlNodeType::Parallel(_) => format!("\"Parallel ({status_tip})\""),
+            ControlNodeType::Composite(c) => c
+                .details()
+                .map(|deets| format!("\"{} {deets} ({status_tip}){memory}\"", c.name()))
+                .unwrap_or(format!("\"{} ({status_tip}){memory}\"", c.name())),
             ControlNodeType::Decorator(d) => {
                 // let name = format!("\"{}\"", d.name());
                 // attrs.push(attr!("xlabel", name));
@@ -432,6 +473,7 @@ impl<D: Decorator> ControlNode<D> {
             }
         };
         attrs.push(attr!("tooltip", tip));
+        attrs.extend(self.memory.graphviz_attrs());
 
         attrs.extend(self.status.graphviz_attrs());
         attrs
@@ -444,6 +486,37 @@ impl GraphvizAttrs for StandardDecorator {
             StandardDecorator::Invert(_) => INVERT_SYMBOL,
             StandardDecorator::Repeat(r) => &format!("{} \n {}", LOOP_SYMBOL, r.retry),
             StandardDecorator::Subtree(_) => SUBTREE_SYMBOL,
+            StandardDecorator::Timeout(t) => &match t.remaining() {
+                Some(remaining) => format!("{TIMEOUT_SYMBOL} \n {remaining:.1?}"),
+                None => format!("{TIMEOUT_SYMBOL} \n {:.1?}", t.duration),
+            },
+            StandardDecorator::RetryWithBackoff(r) => &match r.remaining() {
+                Some(remaining) => format!("{BACKOFF_SYMBOL} \n wait {remaining:.1?}"),
+                None => format!("{BACKOFF_SYMBOL} \n {}", r.retries - r.attempt),
+            },
+            StandardDecorator::Cooldown(c) => &match c.remaining() {
+                Some(remaining) => format!("{COOLDOWN_SYMBOL} \n wait {remaining:.1?}"),
+                None => format!("{COOLDOWN_SYMBOL} \n {:.1?}", c.cooldown),
+            },
+            StandardDecorator::RepeatN(r) => {
+                &format!("{LOOP_SYMBOL} \n {}/{}", r.iterations.count, r.n)
+            }
+            StandardDecorator::RetryUntilSuccessful(r) => {
+                &format!("{RETRY_UNTIL_SUCCESS_SYMBOL} \n {}", r.iterations.count)
+            }
+            StandardDecorator::KeepRunningUntilFailure(k) => {
+                &format!("{KEEP_RUNNING_SYMBOL} \n {}", k.iterations.count)
+            }
+            StandardDecorator::Infinite(i) => {
+                &format!("{INFINITE_SYMBOL} \n {}", i.iterations.count)
+            }
+            StandardDecorator::ForceSuccess(_) => FORCE_SUCCESS_SYMBOL,
+            StandardDecorator::ForceFailure(_) => FORCE_FAILURE_SYMBOL,
+            StandardDecorator::RunOnce(r) => &match r.result {
+                Some(result) => format!("{RUN_ONCE_SYMBOL} \n {result:?}"),
+                None => RUN_ONCE_SYMBOL.to_string(),
+            },
+            StandardDecorator::Guard(g) => &format!("{GUARD_SYMBOL} \n {}", g.name()),
         };
         let symbol = format!("\"{symbol}\"");
 
@@ -451,25 +524,85 @@ impl GraphvizAttrs for StandardDecorator {
     }
 }
 
+impl ParallelPolicy {
+    fn graphviz_label(&self) -> String {
+        let count = |n: Option<usize>| n.map(|n| n.to_string()).unwrap_or("all".to_string());
+        match self {
+            ParallelPolicy::WaitAll => PARALLEL_SYMBOL.to_string(),
+            ParallelPolicy::Threshold { success, failure } => format!(
+                "{PARALLEL_SYMBOL} \n ✓{} ✗{}",
+                count(*success),
+                count(*failure)
+            ),
+            ParallelPolicy::Race => format!("{PARALLEL_SYMBOL} \n race"),
+        }
+    }
+}
+
+/// Nodes that tick their succeeded children again get marked with an external label, remembering
+/// is the default so it's left unmarked.
+impl GraphvizAttrs for Memory {
+    fn graphviz_attrs(&self) -> Vec<Attribute> {
+        let symbol = match self {
+            Memory::Remember => return vec![],
+            Memory::Reevaluate => REEVALUATE_SYMBOL,
+            Memory::ReevaluateConditionsOnly => REEVALUATE_CONDITIONS_SYMBOL,
+        };
+        let symbol = format!("\"{symbol}\"");
+
+        vec![attr!("xlabel", symbol)]
+    }
+}
+
 impl CTreeNodeID {
     pub fn graphviz_id(&self) -> Id {
         id!(format!("CTreeNodeId{}", self.index()))
     }
 }
 
-impl<D: Decorator + GraphvizAttrs> GraphvizAttrs for ControlNodeType<D> {
+impl<D: Decorator + GraphvizAttrs, C: Composite + GraphvizAttrs> GraphvizAttrs
+    for ControlNodeType<D, C>
+{
     fn graphviz_attrs(&self) -> Vec<Attribute> {
-        let mut attrs = vec![];
+        match self {
+            ControlNodeType::Composite(c) => c.graphviz_attrs(),
+            ControlNodeType::Decorator(d) => d.graphviz_attrs(),
+        }
+    }
+}
 
+impl GraphvizAttrs for StandardComposite {
+    fn graphviz_attrs(&self) -> Vec<Attribute> {
         let symbol = match self {
-            ControlNodeType::Sequence(_) => SEQUENCE_SYMBOL,
-            ControlNodeType::Fallback(_) => FALLBACK_SYMBOL,
-            ControlNodeType::Parallel(_) => PARALLEL_SYMBOL,
-            ControlNodeType::Decorator(d) => return d.graphviz_attrs(),
+            StandardComposite::Sequence(_) => SEQUENCE_SYMBOL,
+            StandardComposite::Fallback(_) => FALLBACK_SYMBOL,
+            StandardComposite::Parallel(p) => &p.policy.graphviz_label(),
+            StandardComposite::ReactiveSequence(_) => REACTIVE_SEQUENCE_SYMBOL,
+            StandardComposite::ReactiveFallback(_) => REACTIVE_FALLBACK_SYMBOL,
+            StandardComposite::Switch(s) => &match s.active {
+                Some(active) => format!("{SWITCH_SYMBOL} \n {active}"),
+                None => SWITCH_SYMBOL.to_string(),
+            },
+            StandardComposite::RandomSelector(_) => RANDOM_SYMBOL,
+            StandardComposite::WeightedRandom(w) => &match w.picked {
+                Some(picked) => format!("{WEIGHTED_RANDOM_SYMBOL} \n {picked}"),
+                None => WEIGHTED_RANDOM_SYMBOL.to_string(),
+            },
+            StandardComposite::UtilitySelector(u) => &match u.current() {
+                Some(current) => format!("{UTILITY_SYMBOL} \n {current}"),
+                None => UTILITY_SYMBOL.to_string(),
+            },
+            StandardComposite::IfThenElse(i) => &match i.branch {
+                Some(branch) => format!("{IF_THEN_ELSE_SYMBOL} \n {branch}"),
+                None => IF_THEN_ELSE_SYMBOL.to_string(),
+            },
+            StandardComposite::WhileDoElse(w) => &match w.branch {
+                Some(branch) => format!("{WHILE_DO_ELSE_SYMBOL} \n {branch}"),
+                None => WHILE_DO_ELSE_SYMBOL.to_string(),
+            },
         };
         let symbol = format!("\"{symbol}\"");
-        attrs.push(attr!("label", symbol));
 
-        attrs
+        vec![attr!("label", symbol)]
     }
 }
@@ -0,0 +1,439 @@
+//! # Typed key-value blackboard with declared ports
+//!
+//! Any `Default + Clone + Debug` struct is a [`Blackboard`](crate::traits::Blackboard), which is
+//! the simplest thing that works but ties every leaf to that one struct. [`KvBlackboard`] stores
+//! values by name instead, read & written through typed [`Key`]s, so a leaf only needs to know the
+//! keys it uses and can be reused across trees.
+//!
+//! Leaves declare the keys they read & write as [`Ports`]
+//! ([`Executor::ports`](crate::traits::Executor::ports) /
+//! [`Conditional::ports`](crate::traits::Conditional::ports)), and
+//! [`BTBuilder::build`](crate::bt::builder::BTBuilder::build) checks every input is written by an
+//! earlier leaf or [preset](crate::bt::builder::BTBuilder::preset) by the caller.
+
+use std::any::{type_name, Any, TypeId};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use ahash::HashMap;
+
+use crate::bt::blackboard_decorators::UndoLog;
+
+/// Name of a value in a [`KvBlackboard`], along with its type.
+pub struct Key<T> {
+    name: &'static str,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T> Key<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _type: PhantomData,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+// derives would needlessly require `T` to implement the traits too
+
+impl<T> Clone for Key<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Key<T> {}
+
+impl<T> PartialEq for Key<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl<T> Eq for Key<T> {}
+
+impl<T> Debug for Key<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Key<{}>({:?})", type_name::<T>(), self.name)
+    }
+}
+
+/// A value that can be stored in a [`KvBlackboard`], anything `Clone + Debug + Send + Sync`.
+pub trait Value: Any + Debug + Send + Sync {
+    fn clone_box(&self) -> Box<dyn Value>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+    fn type_name(&self) -> &'static str;
+}
+
+impl<T: Any + Clone + Debug + Send + Sync> Value for T {
+    fn clone_box(&self) -> Box<dyn Value> {
+        Box::new(self.clone())
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn type_name(&self) -> &'static str {
+        type_name::<T>()
+    }
+}
+
+impl Clone for Box<dyn Value> {
+    fn clone(&self) -> Self {
+        self.as_ref().clone_box()
+    }
+}
+
+/// [`Blackboard`](crate::traits::Blackboard) of values stored by name, see [`Key`].
+///
+/// It's an [`UndoLog`], the previous value of a key is recorded on every write while a checkpoint
+/// is open.
+#[derive(Debug, Default, Clone)]
+pub struct KvBlackboard {
+    values: HashMap<&'static str, Box<dyn Value>>,
+
+    /// Previous values of the keys written since the first open checkpoint, `None` if unset
+    undo: Vec<(&'static str, Option<Box<dyn Value>>)>,
+    /// Number of open checkpoints
+    checkpoints: usize,
+}
+
+impl KvBlackboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The value of `key`, `None` if it isn't set or was set with another type
+    pub fn get<T: 'static>(&self, key: Key<T>) -> Option<&T> {
+        self.value(key.name)?.as_any().downcast_ref()
+    }
+
+    /// Mutable access to the value of `key`, see [`Self::get`]
+    pub fn get_mut<T: 'static>(&mut self, key: Key<T>) -> Option<&mut T> {
+        self.get(key)?;
+        self.record(key.name);
+        let value: &mut dyn Value = self.values.get_mut(key.name)?.as_mut();
+        value.as_any_mut().downcast_mut()
+    }
+
+    /// Set `key` to `value`, replacing whatever was there
+    pub fn set<T: Value>(&mut self, key: Key<T>, value: T) {
+        self.record(key.name);
+        self.values.insert(key.name, Box::new(value));
+    }
+
+    /// Builder style [`Self::set`]
+    pub fn with<T: Value>(mut self, key: Key<T>, value: T) -> Self {
+        self.set(key, value);
+        self
+    }
+
+    /// Take the value of `key` out, it's left unset if it had another type
+    pub fn remove<T: 'static>(&mut self, key: Key<T>) -> Option<T> {
+        if !self.value(key.name)?.as_any().is::<T>() {
+            return None;
+        }
+        self.record(key.name);
+        let value = self.values.remove(key.name)?;
+        value.into_any().downcast().ok().map(|value| *value)
+    }
+
+    /// The value stored under `name`, whatever its type
+    pub fn value(&self, name: &str) -> Option<&dyn Value> {
+        // `Box<dyn Value>` is a `Value` too, so it has to be unboxed before downcasting
+        self.values.get(name).map(|value| value.as_ref())
+    }
+
+    /// Whether anything is stored under `name`
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    /// Keep the current value under `name` for a rollback, if a checkpoint is open
+    fn record(&mut self, name: &'static str) {
+        if self.checkpoints > 0 {
+            self.undo.push((name, self.values.get(name).cloned()));
+        }
+    }
+
+    /// The ports of the values that are set, for
+    /// [`BTBuilder::preset`](crate::bt::builder::BTBuilder::preset)
+    pub fn ports(&self) -> impl Iterator<Item = Port> + '_ {
+        self.values.iter().map(|(&name, value)| {
+            let value = value.as_ref();
+            Port {
+                name,
+                type_id: value.as_any().type_id(),
+                type_name: value.type_name(),
+            }
+        })
+    }
+}
+
+impl UndoLog for KvBlackboard {
+    /// Length of the undo log when the checkpoint was taken
+    type Checkpoint = usize;
+
+    fn checkpoint(&mut self) -> usize {
+        self.checkpoints += 1;
+        self.undo.len()
+    }
+
+    fn rollback(&mut self, checkpoint: usize) {
+        while self.undo.len() > checkpoint {
+            let Some((name, previous)) = self.undo.pop() else {
+                break;
+            };
+            match previous {
+                Some(value) => self.values.insert(name, value),
+                None => self.values.remove(name),
+            };
+        }
+        self.commit(checkpoint);
+    }
+
+    fn commit(&mut self, _checkpoint: usize) {
+        self.checkpoints = self.checkpoints.saturating_sub(1);
+        // nothing left to roll back to
+        if self.checkpoints == 0 {
+            self.undo.clear();
+        }
+    }
+}
+
+/// A [`Key`] read or written by a leaf, without the type parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Port {
+    pub name: &'static str,
+    pub type_id: TypeId,
+    pub type_name: &'static str,
+}
+
+impl<T: 'static> From<Key<T>> for Port {
+    fn from(key: Key<T>) -> Self {
+        Self {
+            name: key.name,
+            type_id: TypeId::of::<T>(),
+            type_name: type_name::<T>(),
+        }
+    }
+}
+
+/// The keys a leaf reads (inputs) & writes (outputs).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Ports {
+    pub inputs: Vec<Port>,
+    pub outputs: Vec<Port>,
+}
+
+impl Ports {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// The leaf reads `key`
+    pub fn input(mut self, key: impl Into<Port>) -> Self {
+        self.inputs.push(key.into());
+        self
+    }
+    /// The leaf writes `key`
+    pub fn output(mut self, key: impl Into<Port>) -> Self {
+        self.outputs.push(key.into());
+        self
+    }
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty() && self.outputs.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bt::ShrubberyBT;
+    use crate::traits::*;
+    use crate::{ShrubberyError, Status};
+
+    const START: Key<u32> = Key::new("start");
+    const MIDDLE: Key<u32> = Key::new("middle");
+    const END: Key<u32> = Key::new("end");
+
+    #[test]
+    fn typed_keys() {
+        let mut blackboard = KvBlackboard::new().with(START, 1);
+        assert_eq!(blackboard.get(START), Some(&1));
+        assert_eq!(blackboard.get(END), None);
+
+        *blackboard.get_mut(START).unwrap() += 1;
+        assert_eq!(blackboard.get(START), Some(&2));
+
+        // same name, different type
+        let wrong: Key<f32> = Key::new("start");
+        assert_eq!(blackboard.get(wrong), None);
+        assert_eq!(blackboard.remove(wrong), None);
+        assert!(blackboard.contains("start"));
+
+        assert_eq!(blackboard.clone().remove(START), Some(2));
+        assert_eq!(blackboard.ports().collect::<Vec<_>>(), vec![START.into()]);
+    }
+
+    #[test]
+    fn undo_log() {
+        let mut blackboard = KvBlackboard::new().with(START, 1);
+        let outer = blackboard.checkpoint();
+        blackboard.set(START, 2);
+
+        let inner = blackboard.checkpoint();
+        blackboard.set(MIDDLE, 3);
+        *blackboard.get_mut(START).unwrap() += 1;
+        blackboard.rollback(inner);
+        assert_eq!(blackboard.get(START), Some(&2));
+        assert_eq!(blackboard.get(MIDDLE), None);
+
+        let inner = blackboard.checkpoint();
+        blackboard.remove(START);
+        blackboard.set(END, 4);
+        blackboard.commit(inner);
+        assert_eq!(blackboard.get(START), None);
+
+        // the committed changes are still undone by the outer checkpoint
+        blackboard.rollback(outer);
+        assert_eq!(blackboard.get(START), Some(&1));
+        assert_eq!(blackboard.get(END), None);
+        assert!(blackboard.undo.is_empty());
+    }
+
+    /// Reusable leaf, adds one to `from` and writes it to `to`
+    #[derive(Debug, Clone)]
+    struct Increment {
+        from: Key<u32>,
+        to: Key<u32>,
+    }
+
+    impl Executor<KvBlackboard> for Increment {
+        fn execute(&self, blackboard: &mut KvBlackboard) -> Status {
+            let Some(&value) = blackboard.get(self.from) else {
+                return Status::Failure;
+            };
+            blackboard.set(self.to, value + 1);
+            Status::Success
+        }
+        fn ports(&self) -> Ports {
+            Ports::new().input(self.from).output(self.to)
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct IsSet(Key<u32>);
+
+    impl Conditional<KvBlackboard> for IsSet {
+        fn conditional(&self, blackboard: &KvBlackboard) -> Status {
+            blackboard.get(self.0).is_some().into()
+        }
+        fn ports(&self) -> Ports {
+            Ports::new().input(self.0)
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct KvHandler;
+
+    impl ActionHandler for KvHandler {
+        type Bb = KvBlackboard;
+        type Execute = Increment;
+        type Condition = IsSet;
+    }
+
+    #[test]
+    fn ports() {
+        let mut builder = ShrubberyBT::<KvHandler>::builder();
+        builder.layer(|mut root| {
+            root.execute(Increment {
+                from: START,
+                to: MIDDLE,
+            });
+            root.condition(IsSet(MIDDLE));
+            root.execute(Increment {
+                from: MIDDLE,
+                to: END,
+            });
+        });
+        let mut blackboard = KvBlackboard::new().with(START, 1);
+        builder.preset_blackboard(&blackboard);
+        let mut bt = builder.build().unwrap();
+
+        assert_eq!(bt.tick(&mut blackboard), Status::Success);
+        assert_eq!(blackboard.get(END), Some(&3));
+    }
+
+    #[test]
+    fn undo_transaction() {
+        let mut builder = ShrubberyBT::<KvHandler>::builder();
+        builder.layer(|mut root| {
+            root.fallback(|mut fallback| {
+                fallback.undo_transaction(|mut attempt| {
+                    attempt.execute(Increment {
+                        from: START,
+                        to: START,
+                    });
+                    attempt.execute(Increment {
+                        from: START,
+                        to: MIDDLE,
+                    });
+                    attempt.condition(IsSet(END));
+                });
+                fallback.execute(Increment {
+                    from: START,
+                    to: END,
+                });
+            });
+        });
+        let mut blackboard = KvBlackboard::new().with(START, 1);
+        builder.preset_blackboard(&blackboard);
+        builder.preset(END);
+        let mut bt = builder.build().unwrap();
+
+        assert_eq!(bt.tick(&mut blackboard), Status::Success);
+        assert_eq!(blackboard.get(START), Some(&1));
+        assert_eq!(blackboard.get(MIDDLE), None);
+        assert_eq!(blackboard.get(END), Some(&2));
+    }
+
+    #[test]
+    fn missing_ports() {
+        let mut builder = ShrubberyBT::<KvHandler>::builder();
+        builder.layer(|mut root| {
+            root.condition(IsSet(MIDDLE));
+            root.execute(Increment {
+                from: START,
+                to: MIDDLE,
+            });
+        });
+        builder.preset(START);
+        let err = builder.build().unwrap_err();
+        assert!(matches!(
+            err,
+            ShrubberyError::InvalidPort { port, .. } if port == MIDDLE.into()
+        ));
+
+        // preset with the wrong type
+        let mut builder = ShrubberyBT::<KvHandler>::builder();
+        builder.layer(|mut root| {
+            root.execute(Increment {
+                from: START,
+                to: MIDDLE,
+            });
+        });
+        builder.preset(Key::<f32>::new("start"));
+        let err = builder.build().unwrap_err();
+        assert!(err.to_string().contains("read as u32, written as f32"));
+    }
+}
@@ -75,20 +75,32 @@
 use std::fmt::Debug;
 
 use control::CTreeNodeID;
+use kv_blackboard::Port;
 use thiserror::Error;
 
+pub mod async_executor;
 pub mod bt;
+pub mod clock;
+pub mod concurrent;
 pub mod control;
 pub mod executor_mask;
 pub mod graphviz;
+pub mod kv_blackboard;
+pub mod observed;
+pub mod rng;
 pub mod traits;
 
 #[cfg(test)]
 pub mod null_types;
 
 pub mod prelude {
+    pub use crate::async_executor::{AsyncExecutor, AsyncLeaf, AsyncSignal};
+    pub use crate::bt::blackboard_decorators::*;
     pub use crate::bt::builder::*;
+    pub use crate::bt::lens::{Lens, Lensed};
     pub use crate::bt::ShrubberyBT;
+    pub use crate::clock::{Clock, ClockHandle, ManualClock, SystemClock};
+    pub use crate::concurrent::{ConcurrentTaskHook, ScopedThreads, SplitBlackboard, ThreadPool};
     pub use crate::control::control_nodes::*;
     pub use crate::control::decorators::*;
     pub use crate::control::simple_executors::LeafLogger;
@@ -96,6 +108,9 @@ pub mod prelude {
     pub use crate::control::LeafNode;
     pub use crate::control::RootNode;
     pub use crate::control::StdControlTree;
+    pub use crate::kv_blackboard::{Key, KvBlackboard, Port, Ports};
+    pub use crate::observed::{Mutation, ObservedBlackboard, ObservingHook};
+    pub use crate::rng::{Rng, RngHandle, SplitMix64};
     pub use crate::traits::*;
 
     pub use crate::{ShrubberyError, ShrubberyResult, Status};
@@ -116,6 +131,20 @@ pub enum ShrubberyError {
         decorator: CTreeNodeID,
         children: Vec<CTreeNodeID>,
     },
+
+    #[error("ShrubberyError: Invalid composite: {reason}\n{composite:?} -> {children:?}")]
+    InvalidComposite {
+        composite: CTreeNodeID,
+        children: Vec<CTreeNodeID>,
+        reason: String,
+    },
+
+    #[error("ShrubberyError: Invalid input port {:?} of {leaf:?}: {reason}", port.name)]
+    InvalidPort {
+        leaf: CTreeNodeID,
+        port: Port,
+        reason: String,
+    },
 }
 
 pub type ShrubberyResult<T> = Result<T, ShrubberyError>;
@@ -0,0 +1,466 @@
+//! # Blackboard change notifications
+//!
+//! [`ObservedBlackboard`] wraps a blackboard & records which leaf changed it, by comparing the
+//! blackboard before & after each leaf is ticked. Watches on a field (or any predicate comparing
+//! the before & after) call back as soon as a leaf changes it, e.g. to wake a tree that is
+//! waiting on it.
+//!
+//! The blackboard is only compared around leaves ticked through an [`ObservingHook`], see
+//! [`ShrubberyBT::tick_observed`], which also hands every [`Mutation`] to
+//! [`UpdateCallback::mutation`] as it happens.
+
+use std::fmt::Debug;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::bt::ShrubberyBT;
+use crate::control::{CTreeNodeID, LeafNode};
+use crate::executor_mask::TaskHook;
+use crate::traits::*;
+use crate::Status;
+
+/// A leaf that changed the blackboard when it was ticked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mutation {
+    pub leaf: CTreeNodeID,
+
+    /// Names of the watches it triggered, empty if it only changed unwatched parts of the
+    /// blackboard
+    pub watches: Vec<&'static str>,
+}
+
+type Changed<BB> = Arc<dyn Fn(&BB, &BB) -> bool + Send + Sync>;
+type OnChange<BB> = Arc<dyn Fn(&BB, CTreeNodeID) + Send + Sync>;
+
+/// See [`ObservedBlackboard::subscribe`].
+#[derive(Clone)]
+struct Watch<BB> {
+    name: &'static str,
+    /// Called with the blackboard before & after the leaf was ticked
+    changed: Changed<BB>,
+    callback: OnChange<BB>,
+}
+
+impl<BB> Debug for Watch<BB> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Watch").field("name", &self.name).finish()
+    }
+}
+
+/// [`Blackboard`] that records every leaf that changes `BB`, see the [module docs](self).
+///
+/// It derefs to `BB`, so executors & conditionals read it as usual. Only `BB` is part of the
+/// blackboard's state: clones share the log & the watches, & compare equal if their `BB`s do, so
+/// restoring a snapshot (e.g. a [`Transaction`](crate::bt::blackboard_decorators::Transaction))
+/// leaves them alone.
+#[derive(Debug, Default, Clone)]
+pub struct ObservedBlackboard<BB> {
+    inner: BB,
+    observers: Arc<Mutex<Observers<BB>>>,
+}
+
+/// The part of an [`ObservedBlackboard`] its clones share.
+#[derive(Debug, Default)]
+struct Observers<BB> {
+    log: Vec<Mutation>,
+    watches: Vec<Watch<BB>>,
+}
+
+impl<BB: Blackboard + PartialEq> ObservedBlackboard<BB> {
+    pub fn new(inner: BB) -> Self {
+        Self {
+            inner,
+            observers: Default::default(),
+        }
+    }
+
+    pub fn into_inner(self) -> BB {
+        self.inner
+    }
+
+    fn observers(&self) -> MutexGuard<'_, Observers<BB>> {
+        // a watch that panicked can't leave the log or the watches half updated
+        self.observers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Call `callback` with the blackboard & the leaf whenever a leaf makes `changed(before,
+    /// after)` true
+    pub fn subscribe(
+        &mut self,
+        name: &'static str,
+        changed: impl Fn(&BB, &BB) -> bool + Send + Sync + 'static,
+        callback: impl Fn(&BB, CTreeNodeID) + Send + Sync + 'static,
+    ) {
+        self.observers().watches.push(Watch {
+            name,
+            changed: Arc::new(changed),
+            callback: Arc::new(callback),
+        });
+    }
+
+    /// [`Self::subscribe`] to the changes of a single field
+    pub fn watch<T: PartialEq + 'static>(
+        &mut self,
+        name: &'static str,
+        field: fn(&BB) -> &T,
+        callback: impl Fn(&BB, CTreeNodeID) + Send + Sync + 'static,
+    ) where
+        BB: 'static,
+    {
+        self.subscribe(
+            name,
+            move |before, after| field(before) != field(after),
+            callback,
+        );
+    }
+
+    /// Every change recorded since the last [`ShrubberyBT::tick_observed`] started, oldest first
+    pub fn log(&self) -> Vec<Mutation> {
+        self.observers().log.clone()
+    }
+
+    /// Take the recorded changes, leaving the log empty
+    pub fn take_log(&mut self) -> Vec<Mutation> {
+        std::mem::take(&mut self.observers().log)
+    }
+
+    /// Log `leaf` if the blackboard changed since `before` & call the triggered watches
+    pub fn record(&mut self, leaf: CTreeNodeID, before: &BB) -> Option<Mutation> {
+        if *before == self.inner {
+            return None;
+        }
+        let triggered: Vec<_> = self
+            .observers()
+            .watches
+            .iter()
+            .filter(|watch| (watch.changed)(before, &self.inner))
+            .cloned()
+            .collect();
+        // without holding the lock, in case a callback reads the log
+        for watch in triggered.iter() {
+            (watch.callback)(&self.inner, leaf);
+        }
+        let mutation = Mutation {
+            leaf,
+            watches: triggered.iter().map(|watch| watch.name).collect(),
+        };
+        self.observers().log.push(mutation.clone());
+        Some(mutation)
+    }
+}
+
+impl<BB: PartialEq> PartialEq for ObservedBlackboard<BB> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<BB> Deref for ObservedBlackboard<BB> {
+    type Target = BB;
+    fn deref(&self) -> &BB {
+        &self.inner
+    }
+}
+
+impl<BB> DerefMut for ObservedBlackboard<BB> {
+    fn deref_mut(&mut self) -> &mut BB {
+        &mut self.inner
+    }
+}
+
+/// [`TaskHook`] that records the changes every leaf makes to an [`ObservedBlackboard`].
+pub struct ObservingHook<'a, H: ActionHandler> {
+    pub tasks: TaskHook<'a, H>,
+
+    /// Recorded since the control tree last asked for them, see [`ExecutorHook::mutations`]
+    pending: Vec<Mutation>,
+}
+
+impl<'a, H: ActionHandler> ObservingHook<'a, H> {
+    pub fn new(tasks: TaskHook<'a, H>) -> Self {
+        Self {
+            tasks,
+            pending: vec![],
+        }
+    }
+}
+
+impl<BB, H> ObservingHook<'_, H>
+where
+    BB: Blackboard + PartialEq,
+    H: ActionHandler<Bb = ObservedBlackboard<BB>>,
+{
+    /// Record the changes `leaf` made since the blackboard was `before`
+    fn record(&mut self, leaf: &LeafNode, before: &BB) {
+        let Some(id) = leaf.id else {
+            return;
+        };
+        if let Some(mutation) = self.tasks.blackboard.record(id, before) {
+            self.pending.push(mutation);
+        }
+    }
+}
+
+impl<BB, H> ExecutorHook for ObservingHook<'_, H>
+where
+    BB: Blackboard + PartialEq,
+    H: ActionHandler<Bb = ObservedBlackboard<BB>>,
+{
+    fn hook(&mut self, leaf: &LeafNode) -> Status {
+        let before = self.tasks.blackboard.inner.clone();
+        let status = self.tasks.hook(leaf);
+        self.record(leaf, &before);
+        status
+    }
+
+    fn halt(&mut self, leaf: &LeafNode) {
+        let before = self.tasks.blackboard.inner.clone();
+        self.tasks.halt(leaf);
+        self.record(leaf, &before);
+    }
+
+    fn pre_tick(&mut self, node_id: CTreeNodeID) -> PreTick {
+        self.tasks.pre_tick(node_id)
+    }
+
+    fn post_tick(&mut self, node_id: CTreeNodeID, status: Status) {
+        self.tasks.post_tick(node_id, status);
+    }
+
+    fn halt_control(&mut self, node_id: CTreeNodeID) {
+        self.tasks.halt_control(node_id);
+    }
+
+    fn select(&mut self, node_id: CTreeNodeID, children: usize) -> Option<Selection> {
+        self.tasks.select(node_id, children)
+    }
+
+    fn mutations(&mut self) -> Vec<Mutation> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+impl<BB, H, D, C> ShrubberyBT<H, D, C>
+where
+    BB: Blackboard + PartialEq,
+    H: ActionHandler<Bb = ObservedBlackboard<BB>>,
+    D: Decorator,
+    C: Composite,
+{
+    /// [`Self::tick`], recording the changes each leaf makes to the blackboard & handing them to
+    /// `cb` as they happen. The [log](ObservedBlackboard::log) of the previous tick is cleared
+    /// first.
+    pub fn tick_observed(
+        &mut self,
+        blackboard: &mut H::Bb,
+        cb: &mut impl UpdateCallback<D, C>,
+    ) -> Status {
+        // only keep the log of the latest tick, so it doesn't grow for as long as the tree runs
+        blackboard.take_log();
+        let mut hook = ObservingHook::new(TaskHook::new(&mut self.dispatch, blackboard));
+        self.control_tree.tick_with_update_callback(&mut hook, cb)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+    use crate::control::control_nodes::ParallelPolicy;
+    use crate::control::{ControlTree, ROOT_ID};
+    use crate::prelude::StandardDecorator;
+
+    #[derive(Debug, Default, Clone, PartialEq)]
+    struct Robot {
+        battery: u32,
+        position: i32,
+    }
+
+    #[derive(Debug, Clone)]
+    enum Step {
+        Drive(i32),
+        Wait,
+        /// Runs until it's halted, then drives back to the start
+        Park,
+    }
+
+    impl Executor<ObservedBlackboard<Robot>> for Step {
+        fn execute(&self, robot: &mut ObservedBlackboard<Robot>) -> Status {
+            match self {
+                Step::Drive(distance) => {
+                    robot.position += distance;
+                    robot.battery -= 1;
+                }
+                Step::Wait => {}
+                Step::Park => return Status::Running,
+            }
+            Status::Success
+        }
+        fn halt(&self, robot: &mut ObservedBlackboard<Robot>) {
+            robot.position = 0;
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct Charged;
+
+    impl Conditional<ObservedBlackboard<Robot>> for Charged {
+        fn conditional(&self, robot: &ObservedBlackboard<Robot>) -> Status {
+            (robot.battery > 0).into()
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct RobotHandler;
+
+    impl ActionHandler for RobotHandler {
+        type Bb = ObservedBlackboard<Robot>;
+        type Execute = Step;
+        type Condition = Charged;
+    }
+
+    #[derive(Default)]
+    struct Mutations(Vec<Mutation>);
+
+    impl UpdateCallback<StandardDecorator> for Mutations {
+        fn callback(&mut self, _state: &ControlTree<StandardDecorator>) {}
+        fn mutation(&mut self, mutation: &Mutation) {
+            self.0.push(mutation.clone());
+        }
+    }
+
+    #[test]
+    fn observed_blackboard() {
+        let mut builder = ShrubberyBT::<RobotHandler>::builder();
+        builder.layer(|mut root| {
+            root.condition(Charged);
+            root.execute(Step::Wait);
+            root.execute(Step::Drive(3));
+            root.execute(Step::Drive(0));
+        });
+        let mut bt = builder.build().unwrap();
+
+        let moved = Arc::new(AtomicU32::new(0));
+        let mut robot = ObservedBlackboard::new(Robot {
+            battery: 10,
+            position: 0,
+        });
+        let counter = moved.clone();
+        robot.watch(
+            "position",
+            |robot| &robot.position,
+            move |_, _| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            },
+        );
+        robot.subscribe(
+            "low battery",
+            |before, after| before.battery > 8 && after.battery <= 8,
+            |_, _| {},
+        );
+
+        let mut mutations = Mutations::default();
+        assert_eq!(
+            bt.tick_observed(&mut robot, &mut mutations),
+            Status::Success
+        );
+
+        let drive = bt.control_tree.children(&ROOT_ID)[2];
+        let stay = bt.control_tree.children(&ROOT_ID)[3];
+        let expected = vec![
+            Mutation {
+                leaf: drive,
+                watches: vec!["position"],
+            },
+            // only the battery changed
+            Mutation {
+                leaf: stay,
+                watches: vec!["low battery"],
+            },
+        ];
+        assert_eq!(robot.log(), expected);
+        assert_eq!(mutations.0, expected);
+        assert_eq!(moved.load(Ordering::Relaxed), 1);
+
+        assert_eq!(robot.take_log().len(), 2);
+        assert!(robot.log().is_empty());
+        assert_eq!(robot.into_inner().position, 3);
+    }
+
+    #[test]
+    fn transaction_keeps_log() {
+        let mut builder = ShrubberyBT::<RobotHandler>::builder();
+        let drive = builder.layer(|mut root| {
+            root.transaction(|mut attempt| {
+                let drive = attempt.execute(Step::Drive(3));
+                attempt.condition(Charged);
+                drive
+            })
+        });
+        let mut bt = builder.build().unwrap();
+
+        let moved = Arc::new(AtomicU32::new(0));
+        let mut robot = ObservedBlackboard::new(Robot {
+            battery: 1,
+            position: 0,
+        });
+        let counter = moved.clone();
+        robot.watch(
+            "position",
+            |robot| &robot.position,
+            move |_, _| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            },
+        );
+        let expected = vec![Mutation {
+            leaf: drive,
+            watches: vec!["position"],
+        }];
+
+        // the drive is rolled back, but not the log or the watch
+        for ticks in 1..=2 {
+            assert_eq!(
+                bt.tick_observed(&mut robot, &mut Mutations::default()),
+                Status::Failure
+            );
+            assert_eq!(robot.position, 0);
+            // only the latest tick is logged
+            assert_eq!(robot.log(), expected);
+            assert_eq!(moved.load(Ordering::Relaxed), ticks);
+        }
+    }
+
+    #[test]
+    fn halt_mutations() {
+        let mut builder = ShrubberyBT::<RobotHandler>::builder();
+        let park = builder.layer(|mut root| {
+            let fail_on_one = ParallelPolicy::threshold(None, Some(1));
+            root.parallel_policy(fail_on_one, |mut both| {
+                let park = both.execute(Step::Park);
+                both.condition(Charged);
+                park
+            })
+        });
+        let mut bt = builder.build().unwrap();
+        let mut robot = ObservedBlackboard::new(Robot {
+            battery: 0,
+            position: 5,
+        });
+
+        // the parallel fails as soon as the robot isn't charged, & parking is halted
+        let mut mutations = Mutations::default();
+        assert_eq!(
+            bt.tick_observed(&mut robot, &mut mutations),
+            Status::Failure
+        );
+        let expected = vec![Mutation {
+            leaf: park,
+            watches: vec![],
+        }];
+        assert_eq!(robot.log(), expected);
+        assert_eq!(mutations.0, expected);
+    }
+}
@@ -0,0 +1,142 @@
+//! # Seedable randomness for nodes that need it
+//!
+//! Nodes never reach for a global RNG, they own a [`SplitMix64`] (or any other [`Rng`], through a
+//! [`RngHandle`]) seeded by the caller, so a tree built with the same seeds always makes the same
+//! choices.
+
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+/// Source of randomness for the nodes that need it, implement it to plug in another generator.
+pub trait Rng: Debug + Send {
+    fn next_u64(&mut self) -> u64;
+
+    /// Uniform in `0..n`, `0` if `n` is `0`.
+    fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            return 0;
+        }
+        // multiply-shift maps the full u64 range onto 0..n without modulo bias worth caring about
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+
+    /// Uniform in `0.0..1.0`
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Shuffle `items` in place (Fisher-Yates)
+    fn shuffle(&mut self, items: &mut [usize]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i as u64 + 1) as usize);
+        }
+    }
+}
+
+/// Small, fast, seedable pseudo random number generator ([SplitMix64]). Not suitable for
+/// anything cryptographic.
+///
+/// [SplitMix64]: https://prng.di.unimi.it/splitmix64.c
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+}
+
+impl Rng for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Shared handle to the [`Rng`] of a node. Clones of the handle (and so clones of the tree) draw
+/// from the same generator.
+#[derive(Debug, Clone)]
+pub struct RngHandle(Arc<Mutex<dyn Rng>>);
+
+impl RngHandle {
+    pub fn new(rng: impl Rng + 'static) -> Self {
+        Self(Arc::new(Mutex::new(rng)))
+    }
+
+    /// A [`SplitMix64`] seeded with `seed`
+    pub fn seeded(seed: u64) -> Self {
+        Self::new(SplitMix64::new(seed))
+    }
+
+    /// Borrow the generator
+    pub fn with<O>(&self, f: impl FnOnce(&mut dyn Rng) -> O) -> O {
+        let mut rng = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut *rng)
+    }
+}
+
+impl<R: Rng + 'static> From<R> for RngHandle {
+    fn from(rng: R) -> Self {
+        Self::new(rng)
+    }
+}
+
+impl PartialEq for RngHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for RngHandle {}
+
+impl Hash for RngHandle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as *const () as usize).hash(state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn seeded() {
+        let mut a = SplitMix64::new(7);
+        let mut b = SplitMix64::new(7);
+        let a: Vec<_> = (0..8).map(|_| a.next_u64()).collect();
+        let b: Vec<_> = (0..8).map(|_| b.next_u64()).collect();
+        assert_eq!(a, b);
+        assert_ne!(a, {
+            let mut c = SplitMix64::new(8);
+            (0..8).map(|_| c.next_u64()).collect::<Vec<_>>()
+        });
+    }
+
+    #[test]
+    fn bounded() {
+        let mut rng = SplitMix64::new(0);
+        for _ in 0..1000 {
+            assert!(rng.below(5) < 5);
+            assert!((0.0..1.0).contains(&rng.next_f64()));
+        }
+        assert_eq!(rng.below(0), 0);
+    }
+
+    #[test]
+    fn shuffled() {
+        let mut order: Vec<usize> = (0..8).collect();
+        SplitMix64::new(3).shuffle(&mut order);
+        assert_ne!(order, (0..8).collect::<Vec<_>>());
+        order.sort();
+        assert_eq!(order, (0..8).collect::<Vec<_>>());
+    }
+}
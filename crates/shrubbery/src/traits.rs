@@ -1,6 +1,9 @@
 use std::fmt::Debug;
 
+use crate::control::control_nodes::StandardComposite;
 use crate::control::{CTreeNodeID, ChildUpdate, ControlTree, LeafNode};
+use crate::kv_blackboard::Ports;
+use crate::observed::Mutation;
 use crate::Status;
 
 pub trait Control {
@@ -16,17 +19,247 @@ pub trait Control {
     /// [`Sequence`](crate::control::control_nodes::Sequence) and others need to know when to
     /// return success, otherwise they will be stuck [`Status::Running`] forever.
     fn all_children_seen(&mut self) {}
+
+    /// Stop visiting the remaining children for this traversal, even though the node is still
+    /// [`Status::Running`].
+    ///
+    /// Default implementation never yields, but
+    /// [`ReactiveSequence`](crate::control::control_nodes::ReactiveSequence) and
+    /// [`ReactiveFallback`](crate::control::control_nodes::ReactiveFallback) yield at the first
+    /// running child so the guards before it are re-checked before it's ticked again.
+    fn yielded(&self) -> bool {
+        false
+    }
+
+    /// Re-tick [`Conditional`] children every traversal, even if they already succeeded. This
+    /// decides the default [`Memory`](crate::control::control_nodes::Memory) of the node.
+    fn reevaluate_conditions(&self) -> bool {
+        false
+    }
+
+    /// Hand the leaf children to [`ExecutorHook::hook_concurrent`] as a single batch, rather than
+    /// ticking them one after another.
+    fn runs_concurrently(&self) -> bool {
+        false
+    }
+
+    /// Visit only these children (by index into `children`) in this order, rather than all of them
+    /// in the order they were added. Children that are left out are halted if they are still
+    /// running.
+    ///
+    /// `selected` is what [`ExecutorHook::select`] picked from the blackboard, if anything.
+    fn visit_order(
+        &mut self,
+        _children: &[CTreeNodeID],
+        _selected: Option<Selection>,
+    ) -> Option<Vec<usize>> {
+        None
+    }
+
+    /// Pass over `child` in this traversal, checked right before it would be ticked. Like the
+    /// children left out of [`Self::visit_order`], it's halted if it's still running.
+    ///
+    /// [`IfThenElse`](crate::control::control_nodes::IfThenElse) uses this to pass over the
+    /// branch its condition didn't pick.
+    fn skip(&self, _child: CTreeNodeID) -> bool {
+        false
+    }
+
+    /// Start `child` over every traversal once it has finished, whatever the
+    /// [`Memory`](crate::control::control_nodes::Memory) of the node.
+    ///
+    /// [`WhileDoElse`](crate::control::control_nodes::WhileDoElse) uses this to check its
+    /// condition again, even if it's a whole branch rather than a [`Conditional`] leaf.
+    fn reevaluate(&self, _child: CTreeNodeID) -> bool {
+        false
+    }
 }
 
 /// Connector types that define what to do when the [`ControlTree`] ticks a leaf node.
 pub trait ExecutorHook {
     fn hook(&mut self, leaf: &LeafNode) -> Status;
+
+    /// Called for every [`Status::Running`] leaf in a branch that is aborted or reset (see
+    /// [`ControlTree::halt`]), so whatever the leaf started can be stopped.
+    fn halt(&mut self, _leaf: &LeafNode) {}
+
+    /// Tick a batch of leaves that are allowed to run at the same time (the leaf children of a
+    /// concurrent [`Parallel`](crate::control::control_nodes::Parallel)), returning their statuses
+    /// in the same order.
+    ///
+    /// Runs them one after another by default, see
+    /// [`ConcurrentTaskHook`](crate::concurrent::ConcurrentTaskHook) for one that doesn't.
+    fn hook_concurrent(&mut self, leaves: &[&LeafNode]) -> Vec<Status> {
+        leaves.iter().map(|leaf| self.hook(leaf)).collect()
+    }
+
+    /// Called before the branch under a control node is ticked, see [`BlackboardDecorator`].
+    fn pre_tick(&mut self, _node_id: CTreeNodeID) -> PreTick {
+        PreTick::Tick
+    }
+
+    /// Called after the branch under a control node is ticked, with the status of the node, see
+    /// [`BlackboardDecorator::post_tick`].
+    fn post_tick(&mut self, _node_id: CTreeNodeID, _status: Status) {}
+
+    /// Called for every [`Status::Running`] control node in a branch that is aborted or reset, like
+    /// [`Self::halt`] is for leaves.
+    fn halt_control(&mut self, _node_id: CTreeNodeID) {}
+
+    /// Called before the `children` of a control node are visited, see [`BlackboardSelector`].
+    fn select(&mut self, _node_id: CTreeNodeID, _children: usize) -> Option<Selection> {
+        None
+    }
+
+    /// The changes to the blackboard since the last call, handed to
+    /// [`UpdateCallback::mutation`] after each leaf is ticked, see
+    /// [`ObservingHook`](crate::observed::ObservingHook).
+    fn mutations(&mut self) -> Vec<Mutation> {
+        vec![]
+    }
+}
+
+/// What to do with a branch, decided by a [`BlackboardDecorator`] before it is ticked.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PreTick {
+    /// Tick the branch as usual
+    #[default]
+    Tick,
+
+    /// Leave the branch as it is for this tick, it stays [`Status::Running`]
+    Skip,
+
+    /// Succeed without ticking the branch (halting it if it was running)
+    Succeed,
+
+    /// Fail without ticking the branch (halting it if it was running)
+    Fail,
 }
 
+/// Decorators that can read the [`Blackboard`].
+///
+/// The control tree never sees the blackboard, so these live in the
+/// [`LeafDispatch`](crate::executor_mask::LeafDispatch) next to the executors, attached to a
+/// decorator node in the tree (e.g. a
+/// [`Guard`](crate::control::decorators::Guard)). Every time the branch under that node is
+/// about to be ticked [`Self::pre_tick`] decides whether it actually is.
+///
+/// Most decorators only read the blackboard, the other hooks give write access to the ones that
+/// clean up after their branch, like a
+/// [`Transaction`](crate::bt::blackboard_decorators::Transaction).
+pub trait BlackboardDecorator<BB>: BlackboardDecoratorClone<BB> + Debug {
+    fn pre_tick(&mut self, blackboard: &BB) -> PreTick;
+
+    /// Called right before every [`Self::pre_tick`]
+    fn before_tick(&mut self, _blackboard: &mut BB) {}
+
+    /// Called after the branch was ticked, with the status of the decorator node
+    fn post_tick(&mut self, _blackboard: &mut BB, _status: Status) {}
+
+    /// Called when the branch is halted while it's running, and when the tree is
+    /// [reset](crate::bt::ShrubberyBT::reset) whether it was running or not
+    fn halt(&mut self, _blackboard: &mut BB) {}
+
+    /// Optional name for coloring the decorator node in the [`ControlTree`]
+    fn name(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Declares `$clone`, the supertrait that lets a `Box<dyn $trait<BB>>` be cloned, implemented
+/// for every [`Clone`] implementor of `$trait`.
+macro_rules! clone_box {
+    ($trait:ident, $clone:ident) => {
+        #[doc = concat!(
+            "Lets `Box<dyn ", stringify!($trait), ">` be cloned, implemented for every [`Clone`] [`",
+            stringify!($trait), "`]."
+        )]
+        pub trait $clone<BB> {
+            fn clone_box(&self) -> Box<dyn $trait<BB>>;
+        }
+
+        impl<BB, T: $trait<BB> + Clone + 'static> $clone<BB> for T {
+            fn clone_box(&self) -> Box<dyn $trait<BB>> {
+                Box::new(self.clone())
+            }
+        }
+
+        impl<BB> Clone for Box<dyn $trait<BB>> {
+            fn clone(&self) -> Self {
+                self.clone_box()
+            }
+        }
+    };
+}
+
+clone_box!(BlackboardDecorator, BlackboardDecoratorClone);
+
+/// Which children of a composite to visit, picked by a [`BlackboardSelector`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Selection {
+    /// The children (by index) to visit, in order
+    pub order: Vec<usize>,
+
+    /// Score of every child (by index), for selectors that rank them
+    pub scores: Vec<f32>,
+}
+
+impl Selection {
+    /// Visit the children in `order`
+    pub fn ordered(order: Vec<usize>) -> Self {
+        Self {
+            order,
+            scores: vec![],
+        }
+    }
+
+    /// Visit every child, highest score first
+    pub fn scored(scores: Vec<f32>) -> Self {
+        let mut order: Vec<usize> = (0..scores.len()).collect();
+        order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+        Self { order, scores }
+    }
+}
+
+/// Picks which children of a composite to visit from the [`Blackboard`], e.g. the case of a
+/// [`Switch`](crate::control::control_nodes::Switch).
+///
+/// Like [`BlackboardDecorator`]s these live in the
+/// [`LeafDispatch`](crate::executor_mask::LeafDispatch), attached to the composite node, and
+/// [`Self::select`] is called every time its children are about to be visited. What it returns
+/// is handed to [`Control::visit_order`].
+pub trait BlackboardSelector<BB>: BlackboardSelectorClone<BB> + Debug {
+    /// The children (by index) to visit, in order, see [`Selection`]
+    fn select(&mut self, blackboard: &BB, children: usize) -> Selection;
+}
+
+clone_box!(BlackboardSelector, BlackboardSelectorClone);
+
+/// Decorators modify the status of their single child.
+///
+/// # Lifecycle
+///
+/// The [`ControlNode`](crate::control::control_nodes::ControlNode) holding the decorator calls,
+/// in order:
+///
+/// 1. [`Self::init`] the first time it's ticked after a reset.
+/// 2. [`Self::on_enter`] when it starts running, right after `init` unless the decorator finishes
+///    without running its child (e.g. a cooldown that is still cooling down). It's called again if
+///    the decorator goes back to running after it finished.
+/// 3. [`Self::child_updated`] / [`Self::status`] while it runs.
+/// 4. [`Self::on_exit`] once, with the terminal status, when it stops running. If it's reset
+///    while still running (halted) that status is [`Status::Running`].
+/// 5. [`Self::reset`] (or [`Self::restart`]), after `on_exit`.
 pub trait Decorator: Clone {
-    /// Initialize the decorator
+    /// Initialize the decorator, called on the first tick after a reset
     fn init(&mut self);
 
+    /// The decorator started running
+    fn on_enter(&mut self) {}
+
+    /// The decorator stopped running, with `status` [`Status::Running`] if it was halted
+    fn on_exit(&mut self, _status: Status) {}
+
     /// Apply the decorator to a [`ChildUpdate`]
     fn child_updated(&mut self, update: ChildUpdate) -> Status;
 
@@ -35,38 +268,113 @@ pub trait Decorator: Clone {
 
     fn reset(&mut self);
 
+    /// Called instead of [`Self::reset`] when the whole tree is reset (see
+    /// [`ControlTree::reset`]) rather than just the decorator's branch.
+    fn restart(&mut self) {
+        self.reset();
+    }
+
     fn name(&self) -> String;
 
     fn details(&self) -> Option<String> {
         None
     }
 
+    /// Hold off ticking the child for now, e.g. while waiting to retry. The decorator stays
+    /// [`Status::Running`].
+    fn hold(&self) -> bool {
+        false
+    }
+
     /// Request a group of nodes get reset
     fn reset_request(&mut self) -> Option<CTreeNodeID> {
         None
     }
 }
 
+/// Control nodes with any number of children, which decide how the flow moves between them by
+/// implementing [`Control`]. The built in ones are in
+/// [`StandardComposite`](crate::control::control_nodes::StandardComposite).
+pub trait Composite: Control + Clone {
+    fn reset(&mut self);
+
+    fn name(&self) -> String;
+
+    fn details(&self) -> Option<String> {
+        None
+    }
+
+    /// Check the composite can work with `children`, returning why not otherwise. Called when the
+    /// tree is built.
+    fn validate(&self, _children: &[CTreeNodeID]) -> Result<(), String> {
+        Ok(())
+    }
+}
+
 /// Callback that can be used during the exploration of the [`ControlTree`]. Useful primarily for
 /// debuggers such as the [`GraphvizAnimator`](crate::graphviz::GraphvizAnimator), for diagnosing
 /// the behavior inside the control tree itself, regardless of what the leaf nodes & blackboard are
 /// doing internally.
-pub trait UpdateCallback<D: Decorator> {
+pub trait UpdateCallback<D: Decorator, C: Composite = StandardComposite> {
     /// Called when there are noteworthy events in [`ControlTree::run_from_with_update_callback`]
-    fn callback(&mut self, state: &ControlTree<D>);
+    fn callback(&mut self, state: &ControlTree<D, C>);
+
+    /// Called when a leaf changed the blackboard, see [`ExecutorHook::mutations`]
+    fn mutation(&mut self, _mutation: &Mutation) {}
+
+    /// Called when a [`BlackboardSelector`] scored the children of `node_id` (by index), e.g. for
+    /// a [`UtilitySelector`](crate::control::control_nodes::UtilitySelector)
+    fn scores(&mut self, _node_id: CTreeNodeID, _scores: &[f32]) {}
 }
 
 /// No-op callback
 pub struct NoCallback;
 
-impl<D: Decorator> UpdateCallback<D> for NoCallback {
-    fn callback(&mut self, _state: &ControlTree<D>) {}
+impl<D: Decorator, C: Composite> UpdateCallback<D, C> for NoCallback {
+    fn callback(&mut self, _state: &ControlTree<D, C>) {}
 }
 
 /// Leaf nodes that execute a task & update the state of the [`Blackboard`].
+///
+/// Stateless executors only need to implement [`Self::execute`]. Executors that keep their own
+/// state between ticks (counters, trajectory cursors, timers...) also override the `&mut self`
+/// lifecycle methods, which [`TaskHook`](crate::executor_mask::TaskHook) calls as follows:
+///
+/// - [`Self::on_start`] when the leaf is ticked for the first time (or after it was reset)
+/// - [`Self::on_running`] when the leaf is ticked while it's still [`Status::Running`]
+/// - [`Self::on_end`] once either of the above returns a terminal [`Status`]
+/// - [`Self::on_halted`] if the leaf is halted while [`Status::Running`]
+///
+/// NOTE: If you dispatch to executors through an enum, forward the lifecycle methods to the
+/// variants as well as [`Self::execute`], otherwise the defaults will call `execute` instead.
 pub trait Executor<BB: Blackboard>: Clone + Debug {
+    /// Stateless execution, called by the default lifecycle methods. Executors that override them
+    /// still implement it, e.g. by running a fresh copy of the task from the start.
     fn execute(&self, blackboard: &mut BB) -> Status;
 
+    /// The executor was pre-empted while [`Status::Running`], e.g. a sibling made its
+    /// [`Fallback`](crate::control::control_nodes::Fallback) succeed, or a decorator reset its
+    /// branch. Default implementation does nothing.
+    fn halt(&self, _blackboard: &mut BB) {}
+
+    /// The leaf is being ticked and wasn't already running.
+    fn on_start(&mut self, blackboard: &mut BB) -> Status {
+        self.execute(blackboard)
+    }
+
+    /// The leaf is being ticked again after returning [`Status::Running`].
+    fn on_running(&mut self, blackboard: &mut BB) -> Status {
+        self.execute(blackboard)
+    }
+
+    /// The leaf finished with `status`.
+    fn on_end(&mut self, _blackboard: &mut BB, _status: Status) {}
+
+    /// Mutable counterpart of [`Self::halt`], which it calls by default.
+    fn on_halted(&mut self, blackboard: &mut BB) {
+        self.halt(blackboard)
+    }
+
     /// Optional name for coloring the leaf nodes in the [`ControlTree`]
     fn name(&self) -> Option<String> {
         None
@@ -76,6 +384,12 @@ pub trait Executor<BB: Blackboard>: Clone + Debug {
     fn details(&self) -> Option<String> {
         None
     }
+
+    /// The [`KvBlackboard`](crate::kv_blackboard::KvBlackboard) keys the executor reads &
+    /// writes, checked when the tree is built. None by default.
+    fn ports(&self) -> Ports {
+        Ports::default()
+    }
 }
 
 /// Leaf nodes that read the [`Blackboard`] and return a [`Status`] about it.
@@ -91,6 +405,12 @@ pub trait Conditional<BB: Blackboard>: Clone + Debug {
     fn details(&self) -> Option<String> {
         None
     }
+
+    /// The [`KvBlackboard`](crate::kv_blackboard::KvBlackboard) keys the conditional reads,
+    /// checked when the tree is built. None by default.
+    fn ports(&self) -> Ports {
+        Ports::default()
+    }
 }
 
 /// The blackboard is a shared state of the behavior tree that is updated by [`Executor`] leaf
@@ -1,13 +1,26 @@
 use ahash::HashSet;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use graphviz_rust::dot_generator::*;
+use graphviz_rust::dot_structures::*;
+use graphviz_rust::printer::{DotPrinter, PrinterContext};
+
+use shrubbery::clock::ManualClock;
 use shrubbery::control::control_nodes::ControlNode as CNode;
-use shrubbery::control::decorators::StandardDecorator;
+use shrubbery::control::control_nodes::{Memory, ParallelPolicy};
+use shrubbery::control::decorators::{Backoff, RetryWithBackoff, RunOnce, StandardDecorator};
 use shrubbery::control::ChildUpdate;
 use shrubbery::control::ControlTree as CTree;
 use shrubbery::control::LeafNode;
+use shrubbery::control::LeafType;
 use shrubbery::control::ROOT_ID;
 use shrubbery::control::{simple_executors::*, CTreeNodeID};
-use shrubbery::traits::ExecutorHook;
-use shrubbery::Status;
+use shrubbery::graphviz::GraphvizAttrs;
+use shrubbery::rng::{Rng, RngHandle, SplitMix64};
+use shrubbery::traits::{Composite, Control, Decorator, ExecutorHook};
+use shrubbery::{ShrubberyError, Status};
 
 type ControlNode = CNode<StandardDecorator>;
 type ControlTree = CTree<StandardDecorator>;
@@ -78,6 +91,63 @@ impl FailGiven {
     }
 }
 
+/// [`ExecutorHook`] that leaves the given leaves [`Status::Running`] forever and succeeds
+/// everything else, logging which leaves got halted.
+#[derive(Debug, Default, Clone)]
+pub struct RunForever {
+    pub running: Vec<CTreeNodeID>,
+    pub halted: Vec<CTreeNodeID>,
+    pub logger: LeafLogger,
+}
+
+impl ExecutorHook for RunForever {
+    fn hook(&mut self, leaf: &LeafNode) -> Status {
+        let mut leaf = leaf.clone();
+        let status = if self.running.contains(&leaf.id.unwrap()) {
+            Status::Running
+        } else {
+            Status::Success
+        };
+        leaf.status = Some(status);
+        self.logger.hook(&leaf);
+        status
+    }
+    fn halt(&mut self, leaf: &LeafNode) {
+        self.halted.push(leaf.id.unwrap());
+    }
+}
+
+/// [`ExecutorHook`] that returns the next status from a per-leaf script, repeating the last
+/// status once the script runs out, and logs which leaves got halted.
+#[derive(Debug, Default, Clone)]
+pub struct Scripted {
+    pub scripts: Vec<(CTreeNodeID, Vec<Status>)>,
+    pub halted: Vec<CTreeNodeID>,
+    pub logger: LeafLogger,
+}
+
+impl ExecutorHook for Scripted {
+    fn hook(&mut self, leaf: &LeafNode) -> Status {
+        let mut leaf = leaf.clone();
+        let (_, script) = self
+            .scripts
+            .iter_mut()
+            .find(|(id, _)| *id == leaf.id.unwrap())
+            .expect("leaf has no script");
+        let status = if script.len() > 1 {
+            script.remove(0)
+        } else {
+            script[0]
+        };
+        leaf.status = Some(status);
+        self.logger.hook(&leaf);
+        status
+    }
+    fn halt(&mut self, leaf: &LeafNode) {
+        self.halted.push(leaf.id.unwrap());
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct AlwaysFail {
     pub logger: LeafLogger,
@@ -198,18 +268,19 @@ fn right_branch_inner(status: Status) -> Vec<ChildUpdate> {
     vec![update(status, 9), update(status, 10)]
 }
 
+/// Every leaf is visited once per tick, so the first traversal sees every leaf running and the
+/// second sees them all succeed.
 fn slow_sequence_order() -> Vec<ChildUpdate> {
-    let mut seq = left_branch(Status::Running);
-    seq.extend(left_branch(Status::Success));
-    seq.push(right_branch_first(Status::Running));
-    seq.extend(right_branch_inner(Status::Running));
-    seq.extend(right_branch_inner(Status::Success));
-    seq.push(right_branch_last(Status::Running));
-    seq.push(right_branch_first(Status::Success));
-    seq.push(right_branch_last(Status::Success));
+    let mut seq = vec![];
+    for status in [Status::Running, Status::Success] {
+        seq.extend(left_branch(status));
+        seq.push(right_branch_first(status));
+        seq.extend(right_branch_inner(status));
+        seq.push(right_branch_last(status));
+    }
     seq
 }
-/// Make sure [`ControlNodeType::Sequence`] executes normally when all it's children are
+/// Make sure [`StandardComposite::Sequence`] executes normally when all it's children are
 /// [`Status::Success`]
 #[test]
 fn happy_sequence() {
@@ -234,7 +305,7 @@ fn happy_sequence() {
     assert_eq!(order_executed, expect_leaf_order);
 }
 
-/// Make sure [`ControlNodeType::Sequence`] continues ticking its children while they are
+/// Make sure [`StandardComposite::Sequence`] continues ticking its children while they are
 /// [`Status::Running`]
 #[test]
 fn slow_sequence() {
@@ -265,7 +336,58 @@ fn slow_sequence() {
     );
 }
 
-/// Make sure [`ControlNodeType::Sequence`] fails as soon as a child fails
+/// Make sure [`ControlTree::tick`] only does a single traversal, leaving running leaves for the
+/// next tick.
+#[test]
+fn tick_slow_sequence() {
+    let mut logger = SlowLeaves::default();
+
+    let (mut control_tree, expect_leaf_order) = test_tree(ControlNode::sequence());
+
+    let status = control_tree.tick(&mut logger);
+    assert_eq!(status, Status::Running);
+    assert_eq!(logger.logger.updates.len(), expect_leaf_order.len());
+    assert!(logger
+        .logger
+        .updates
+        .iter()
+        .all(|update| update.status.is_running()));
+
+    let status = control_tree.tick(&mut logger);
+    assert_eq!(status, Status::Success);
+    assert_eq!(logger.logger.updates, slow_sequence_order());
+}
+
+/// A [`StandardComposite::Fallback`] must not fail while one of its children is still running.
+#[test]
+fn tick_slow_fallback() {
+    let mut logger = SlowLeaves::default();
+    let mut control_tree = ControlTree::new();
+    let fallback = control_tree
+        .add_child(ROOT_ID, ControlNode::fallback())
+        .unwrap();
+    let leaf = control_tree
+        .add_child(fallback, LeafNode::default())
+        .unwrap();
+
+    assert_eq!(control_tree.tick(&mut logger), Status::Running);
+    assert_eq!(control_tree.tick(&mut logger), Status::Success);
+    assert_eq!(
+        logger.logger.updates,
+        vec![
+            ChildUpdate {
+                status: Status::Running,
+                child_id: leaf
+            },
+            ChildUpdate {
+                status: Status::Success,
+                child_id: leaf
+            },
+        ]
+    );
+}
+
+/// Make sure [`StandardComposite::Sequence`] fails as soon as a child fails
 #[test]
 fn fail_sequence_fast() {
     let mut logger = AlwaysFail::default();
@@ -282,7 +404,7 @@ fn fail_sequence_fast() {
     assert_eq!(logger.logger.updates.len(), 1);
 }
 
-/// Make sure [`ControlNodeType::Parallel`] runs all children regardless of the success or
+/// Make sure [`StandardComposite::Parallel`] runs all children regardless of the success or
 /// failure.
 #[test]
 fn slow_parallel() {
@@ -456,3 +578,1184 @@ fn nested_repeat() {
     );
     assert_eq!(status, Status::Failure);
 }
+
+/// Running the same tree several times in a row restarts it & gives the same trace each time.
+#[test]
+fn repeated_runs() {
+    const RETRIES: usize = 2;
+    let (mut control_tree, _) = test_tree(ControlNode::sequence());
+    let seq = control_tree.insert_between(
+        ROOT_ID,
+        &control_tree.children(&ROOT_ID),
+        ControlNode::sequence(),
+    );
+    control_tree.insert_between(ROOT_ID, &[seq], ControlNode::repeater(RETRIES));
+
+    let mut first = None;
+    for _ in 0..3 {
+        let mut logger = AlwaysFail::default();
+        assert_eq!(control_tree.run(&mut logger), Status::Failure);
+
+        let updates = logger.logger.updates;
+        assert_eq!(updates.len(), RETRIES + 1);
+        assert_eq!(updates, *first.get_or_insert(updates.clone()));
+    }
+}
+
+/// When a [`StandardComposite::Fallback`] succeeds, the children it left running are halted.
+#[test]
+fn halt_fallback_running_children() {
+    let mut control_tree = ControlTree::new();
+    let fallback = control_tree
+        .add_child(ROOT_ID, ControlNode::fallback())
+        .unwrap();
+    let slow = control_tree
+        .add_child(fallback, LeafNode::default())
+        .unwrap();
+    let fast = control_tree
+        .add_child(fallback, LeafNode::default())
+        .unwrap();
+
+    let mut hook = RunForever {
+        running: vec![slow],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Status::Success);
+    assert_eq!(hook.halted, vec![slow]);
+    assert_eq!(control_tree[slow].status(), None);
+    assert_eq!(control_tree[fast].status(), Some(Status::Success));
+}
+
+/// [`ControlTree::halt`] cancels a running subtree, halting only the running leaves.
+#[test]
+fn halt_subtree() {
+    let (mut control_tree, leaves) = test_tree(ControlNode::sequence());
+    let mut hook = RunForever {
+        running: vec![leaves[1], leaves[5]],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Status::Running);
+    assert!(hook.halted.is_empty());
+
+    // halt the right branch, the left one keeps going
+    let right = control_tree.children(&ROOT_ID)[1];
+    control_tree.halt(right, &mut hook);
+    assert_eq!(hook.halted, vec![leaves[5]]);
+    assert_eq!(control_tree[right].status(), None);
+    assert_eq!(control_tree[leaves[1]].status(), Some(Status::Running));
+}
+
+fn conditional_leaf() -> LeafNode {
+    LeafNode {
+        leaf_type: LeafType::Conditional,
+        ..Default::default()
+    }
+}
+
+/// `root -> test -> [guard, action]`, returns `(tree, guard, action)`
+fn guarded_action(test: ControlNode) -> (ControlTree, CTreeNodeID, CTreeNodeID) {
+    let mut control_tree = ControlTree::new();
+    let node = control_tree.add_child(ROOT_ID, test).unwrap();
+    let guard = control_tree.add_child(node, conditional_leaf()).unwrap();
+    let action = control_tree.add_child(node, LeafNode::default()).unwrap();
+    (control_tree, guard, action)
+}
+
+fn ticks_of(logger: &LeafLogger, id: CTreeNodeID) -> usize {
+    logger.updates.iter().filter(|u| u.child_id == id).count()
+}
+
+/// A [`StandardComposite::ReactiveSequence`] re-checks its guard while the action runs, and halts
+/// the action once the guard fails.
+#[test]
+fn reactive_sequence() {
+    use Status::*;
+    let (mut control_tree, guard, action) = guarded_action(ControlNode::reactive_sequence());
+    let mut hook = Scripted {
+        scripts: vec![
+            (guard, vec![Success, Success, Failure]),
+            (action, vec![Running]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert!(hook.halted.is_empty());
+
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+    assert_eq!(ticks_of(&hook.logger, guard), 3);
+    assert_eq!(ticks_of(&hook.logger, action), 2);
+    assert_eq!(hook.halted, vec![action]);
+}
+
+/// A plain [`StandardComposite::Sequence`] doesn't re-check guards that already succeeded.
+#[test]
+fn sequence_remembers_guards() {
+    use Status::*;
+    let (mut control_tree, guard, action) = guarded_action(ControlNode::sequence());
+    let mut hook = Scripted {
+        scripts: vec![(guard, vec![Success, Failure]), (action, vec![Running])],
+        ..Default::default()
+    };
+
+    for _ in 0..3 {
+        assert_eq!(control_tree.tick(&mut hook), Running);
+    }
+    assert_eq!(ticks_of(&hook.logger, guard), 1);
+    assert_eq!(ticks_of(&hook.logger, action), 3);
+}
+
+/// A sequence that re-evaluates its children re-checks the guard before ticking the action again.
+#[test]
+fn sequence_reevaluate() {
+    use Status::*;
+    let (mut control_tree, guard, action) =
+        guarded_action(ControlNode::sequence().memory(Memory::Reevaluate));
+    let mut hook = Scripted {
+        scripts: vec![
+            (guard, vec![Success, Success, Failure]),
+            (action, vec![Running]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+    assert_eq!(ticks_of(&hook.logger, guard), 3);
+    assert_eq!(hook.halted, vec![action]);
+}
+
+/// [`Memory::ReevaluateConditionsOnly`] re-checks the guard, but not an action that already
+/// succeeded. It's the default for the reactive nodes.
+#[test]
+fn sequence_reevaluate_conditions_only() {
+    use Status::*;
+    let mut control_tree = ControlTree::new();
+    let node = control_tree
+        .add_child(
+            ROOT_ID,
+            ControlNode::sequence().memory(Memory::ReevaluateConditionsOnly),
+        )
+        .unwrap();
+    let setup = control_tree.add_child(node, LeafNode::default()).unwrap();
+    let guard = control_tree.add_child(node, conditional_leaf()).unwrap();
+    let action = control_tree.add_child(node, LeafNode::default()).unwrap();
+    let mut hook = Scripted {
+        scripts: vec![
+            (setup, vec![Success]),
+            (guard, vec![Success, Failure]),
+            (action, vec![Running]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+    assert_eq!(ticks_of(&hook.logger, setup), 1);
+    assert_eq!(ticks_of(&hook.logger, guard), 2);
+
+    assert_eq!(
+        ControlNode::reactive_sequence().memory,
+        Memory::ReevaluateConditionsOnly
+    );
+    assert_eq!(ControlNode::sequence().memory, Memory::Remember);
+}
+
+/// The builders set the [`Memory`] of the layer's node, and it shows up in the dot output.
+#[test]
+fn memory_builder() {
+    let mut builder = ControlTree::builder();
+    builder.layer(|mut root_layer| {
+        root_layer.fallback(|mut fallback_layer| {
+            fallback_layer.memory(Memory::Reevaluate);
+            fallback_layer.leaf_node(LeafNode::default());
+        });
+    });
+    let control_tree = builder.build().unwrap();
+
+    let fallback = control_tree.children(&ROOT_ID)[0];
+    assert_eq!(control_tree[fallback].memory(), Some(Memory::Reevaluate));
+    assert_eq!(control_tree[ROOT_ID].memory(), Some(Memory::Remember));
+
+    let dot = control_tree
+        .graphviz_graph()
+        .print(&mut PrinterContext::default());
+    assert!(dot.contains("memory: Reevaluate"));
+    assert!(dot.contains("xlabel=\"⟳\""));
+}
+
+/// A [`StandardComposite::ReactiveSequence`] stops at the first running child, and halts a later
+/// running child if a guard before it starts running.
+#[test]
+fn reactive_sequence_yields() {
+    use Status::*;
+    let (mut control_tree, guard, action) = guarded_action(ControlNode::reactive_sequence());
+    let node = control_tree.children(&ROOT_ID)[0];
+    let after = control_tree.add_child(node, LeafNode::default()).unwrap();
+    let mut hook = Scripted {
+        scripts: vec![
+            (guard, vec![Success, Running]),
+            (action, vec![Running]),
+            (after, vec![Success]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(ticks_of(&hook.logger, after), 0);
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(hook.halted, vec![action]);
+    assert_eq!(control_tree[action].status(), None);
+}
+
+/// A [`StandardComposite::ReactiveFallback`] keeps trying its higher priority guard while the
+/// action runs, and halts the action once the guard succeeds.
+#[test]
+fn reactive_fallback() {
+    use Status::*;
+    let (mut control_tree, guard, action) = guarded_action(ControlNode::reactive_fallback());
+    let mut hook = Scripted {
+        scripts: vec![
+            (guard, vec![Failure, Failure, Success]),
+            (action, vec![Running]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Success);
+    assert_eq!(ticks_of(&hook.logger, guard), 3);
+    assert_eq!(hook.halted, vec![action]);
+}
+
+/// `root -> test -> [guard, action, otherwise]`, returns `(tree, guard, action, otherwise)`
+fn branch(test: ControlNode) -> (ControlTree, CTreeNodeID, CTreeNodeID, CTreeNodeID) {
+    let (mut control_tree, guard, action) = guarded_action(test);
+    let node = control_tree.children(&ROOT_ID)[0];
+    let otherwise = control_tree.add_child(node, LeafNode::default()).unwrap();
+    (control_tree, guard, action, otherwise)
+}
+
+/// A [`StandardComposite::IfThenElse`] runs the else branch when the condition fails, without
+/// checking the condition again while it runs.
+#[test]
+fn if_then_else() {
+    use Status::*;
+    let (mut control_tree, guard, action, otherwise) = branch(ControlNode::if_then_else());
+    let mut hook = Scripted {
+        scripts: vec![
+            (guard, vec![Failure, Success]),
+            (action, vec![Success]),
+            (otherwise, vec![Running, Success]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Success);
+    assert_eq!(ticks_of(&hook.logger, guard), 1);
+    assert_eq!(ticks_of(&hook.logger, action), 0);
+    assert_eq!(ticks_of(&hook.logger, otherwise), 2);
+}
+
+/// Unlike a fallback, a failing then branch of a [`StandardComposite::IfThenElse`] doesn't fall
+/// through to the else branch.
+#[test]
+fn if_then_else_then_fails() {
+    use Status::*;
+    let (mut control_tree, guard, action, otherwise) = branch(ControlNode::if_then_else());
+    let mut hook = Scripted {
+        scripts: vec![
+            (guard, vec![Success]),
+            (action, vec![Failure]),
+            (otherwise, vec![Success]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+    assert_eq!(ticks_of(&hook.logger, otherwise), 0);
+
+    // without an else branch, a failed condition fails
+    let (mut control_tree, guard, action) = guarded_action(ControlNode::if_then_else());
+    let mut hook = Scripted {
+        scripts: vec![(guard, vec![Failure]), (action, vec![Success])],
+        ..Default::default()
+    };
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+    assert_eq!(ticks_of(&hook.logger, action), 0);
+}
+
+/// A [`StandardComposite::WhileDoElse`] checks its condition every tick, and halts the running
+/// branch when it flips.
+#[test]
+fn while_do_else() {
+    use Status::*;
+    let (mut control_tree, guard, action, otherwise) = branch(ControlNode::while_do_else());
+    let mut hook = Scripted {
+        scripts: vec![
+            (guard, vec![Success, Success, Failure, Running]),
+            (action, vec![Running]),
+            (otherwise, vec![Running]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert!(hook.halted.is_empty());
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(ticks_of(&hook.logger, action), 2);
+    assert_eq!(ticks_of(&hook.logger, otherwise), 1);
+    assert_eq!(hook.halted, vec![action]);
+
+    // neither branch runs while the condition is running
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(ticks_of(&hook.logger, otherwise), 1);
+    assert_eq!(hook.halted, vec![action, otherwise]);
+}
+
+/// The condition of a [`StandardComposite::WhileDoElse`] can be a whole branch, which is started
+/// over every tick.
+#[test]
+fn while_do_else_branch_condition() {
+    use Status::*;
+    let mut control_tree = ControlTree::new();
+    let node = control_tree
+        .add_child(ROOT_ID, ControlNode::while_do_else())
+        .unwrap();
+    let condition = control_tree
+        .add_child(node, ControlNode::sequence())
+        .unwrap();
+    let checks = [(); 2].map(|_| {
+        control_tree
+            .add_child(condition, LeafNode::default())
+            .unwrap()
+    });
+    let action = control_tree.add_child(node, LeafNode::default()).unwrap();
+    let otherwise = control_tree.add_child(node, LeafNode::default()).unwrap();
+    let mut hook = Scripted {
+        scripts: vec![
+            (checks[0], vec![Success]),
+            (checks[1], vec![Success, Success, Failure]),
+            (action, vec![Running]),
+            (otherwise, vec![Running]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(ticks_of(&hook.logger, checks[0]), 2);
+    assert!(hook.halted.is_empty());
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(ticks_of(&hook.logger, action), 2);
+    assert_eq!(ticks_of(&hook.logger, otherwise), 1);
+    assert_eq!(hook.halted, vec![action]);
+}
+
+/// `root -> parallel -> [leaf; n]`, returns `(tree, leaves)`
+fn parallel_leaves(parallel: ControlNode, n: usize) -> (ControlTree, Vec<CTreeNodeID>) {
+    let mut control_tree = ControlTree::new();
+    let node = control_tree.add_child(ROOT_ID, parallel).unwrap();
+    let leaves = (0..n)
+        .map(|_| control_tree.add_child(node, LeafNode::default()).unwrap())
+        .collect();
+    (control_tree, leaves)
+}
+
+/// A 2-of-3 [`StandardComposite::Parallel`] succeeds without waiting on the last child, which gets
+/// halted.
+#[test]
+fn parallel_success_threshold() {
+    use Status::*;
+    let (mut control_tree, leaves) =
+        parallel_leaves(ControlNode::parallel_threshold(Some(2), None), 3);
+    let mut hook = Scripted {
+        scripts: vec![
+            (leaves[0], vec![Running, Success]),
+            (leaves[1], vec![Running]),
+            (leaves[2], vec![Success]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert!(hook.halted.is_empty());
+    assert_eq!(control_tree.tick(&mut hook), Success);
+    assert_eq!(hook.halted, vec![leaves[1]]);
+}
+
+/// Once it's impossible to reach the success threshold the node fails early.
+#[test]
+fn parallel_threshold_unreachable() {
+    use Status::*;
+    let (mut control_tree, leaves) =
+        parallel_leaves(ControlNode::parallel_threshold(Some(2), None), 3);
+    let mut hook = Scripted {
+        scripts: vec![
+            (leaves[0], vec![Failure]),
+            (leaves[1], vec![Failure]),
+            (leaves[2], vec![Running]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+    assert_eq!(hook.halted, vec![leaves[2]]);
+}
+
+/// A failure threshold fails the node part way through a pass.
+#[test]
+fn parallel_failure_threshold() {
+    use Status::*;
+    let (mut control_tree, leaves) =
+        parallel_leaves(ControlNode::parallel_threshold(None, Some(1)), 3);
+    let mut hook = Scripted {
+        scripts: vec![
+            (leaves[0], vec![Running]),
+            (leaves[1], vec![Failure]),
+            (leaves[2], vec![Success]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+    assert_eq!(ticks_of(&hook.logger, leaves[2]), 0);
+    assert_eq!(hook.halted, vec![leaves[0]]);
+}
+
+/// In a race the first child to finish decides the outcome.
+#[test]
+fn parallel_race() {
+    use Status::*;
+    let (mut control_tree, leaves) = parallel_leaves(ControlNode::parallel_race(), 2);
+    let mut hook = Scripted {
+        scripts: vec![
+            (leaves[0], vec![Running]),
+            (leaves[1], vec![Running, Running, Failure]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+    assert_eq!(hook.halted, vec![leaves[0]]);
+}
+
+/// Succeed-on-one only fails once every child has failed.
+#[test]
+fn parallel_succeed_on_one() {
+    use Status::*;
+    let (mut control_tree, leaves) = parallel_leaves(ControlNode::parallel_succeed_on_one(), 2);
+    let mut hook = Scripted {
+        scripts: vec![
+            (leaves[0], vec![Failure]),
+            (leaves[1], vec![Running, Failure]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+    assert!(hook.halted.is_empty());
+
+    let mut hook = Scripted {
+        scripts: vec![
+            (leaves[0], vec![Failure]),
+            (leaves[1], vec![Running, Success]),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Success);
+}
+
+/// A failed child keeps its result while the rest of the [`StandardComposite::Parallel`] runs,
+/// it isn't ticked again & given a chance to succeed.
+#[test]
+fn parallel_skips_failed() {
+    use Status::*;
+    let (mut control_tree, leaves) = parallel_leaves(ControlNode::parallel_succeed_on_one(), 2);
+    let mut hook = Scripted {
+        scripts: vec![
+            (leaves[0], vec![Failure, Success]),
+            (leaves[1], vec![Running, Failure]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+    assert_eq!(ticks_of(&hook.logger, leaves[0]), 1);
+    assert_eq!(ticks_of(&hook.logger, leaves[1]), 2);
+}
+
+/// The leaves of a concurrent [`StandardComposite::Parallel`] all run before the node sees their
+/// results, so the ones still running when the policy decides get halted.
+#[test]
+fn concurrent_parallel_halts_batch() {
+    use Status::*;
+    let (mut control_tree, leaves) = parallel_leaves(
+        ControlNode::parallel_concurrent(ParallelPolicy::threshold(None, Some(1))),
+        3,
+    );
+    let mut hook = Scripted {
+        scripts: vec![
+            (leaves[0], vec![Failure]),
+            (leaves[1], vec![Running]),
+            (leaves[2], vec![Running]),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+    assert_eq!(ticks_of(&hook.logger, leaves[2]), 1);
+    let halted: HashSet<_> = hook.halted.into_iter().collect();
+    assert_eq!(halted, HashSet::from_iter([leaves[1], leaves[2]]));
+}
+
+/// [`Scripted`], logging every batch handed to [`ExecutorHook::hook_concurrent`].
+#[derive(Debug, Default, Clone)]
+pub struct Batched {
+    pub scripted: Scripted,
+    pub batches: Vec<Vec<CTreeNodeID>>,
+}
+
+impl ExecutorHook for Batched {
+    fn hook(&mut self, leaf: &LeafNode) -> Status {
+        self.scripted.hook(leaf)
+    }
+    fn hook_concurrent(&mut self, leaves: &[&LeafNode]) -> Vec<Status> {
+        self.batches
+            .push(leaves.iter().map(|leaf| leaf.id.unwrap()).collect());
+        leaves.iter().map(|leaf| self.hook(leaf)).collect()
+    }
+}
+
+/// With [`Memory::Reevaluate`] the succeeded leaves of a concurrent
+/// [`StandardComposite::Parallel`] are started over in the batch, not ticked one by one after it.
+#[test]
+fn concurrent_parallel_reevaluates_batch() {
+    use Status::*;
+    let (mut control_tree, leaves) = parallel_leaves(
+        ControlNode::parallel_concurrent(ParallelPolicy::WaitAll).memory(Memory::Reevaluate),
+        2,
+    );
+    let mut hook = Batched {
+        scripted: Scripted {
+            scripts: vec![
+                (leaves[0], vec![Success]),
+                (leaves[1], vec![Running, Success]),
+            ],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Success);
+    assert_eq!(hook.batches, vec![leaves.clone(), leaves.clone()]);
+    assert_eq!(ticks_of(&hook.scripted.logger, leaves[0]), 2);
+}
+
+/// `root -> timeout -> leaf`, returns `(tree, leaf)`
+fn timed_leaf(clock: &ManualClock, duration: Duration) -> (ControlTree, CTreeNodeID) {
+    let mut control_tree = ControlTree::new();
+    let timeout = control_tree
+        .add_child(ROOT_ID, ControlNode::timeout(duration, clock.clone()))
+        .unwrap();
+    let leaf = control_tree
+        .add_child(timeout, LeafNode::default())
+        .unwrap();
+    (control_tree, leaf)
+}
+
+/// A [`StandardDecorator::Timeout`] fails & halts a child that runs for too long.
+#[test]
+fn timeout() {
+    let clock = ManualClock::new();
+    let (mut control_tree, leaf) = timed_leaf(&clock, Duration::from_secs(2));
+    let mut hook = RunForever {
+        running: vec![leaf],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Status::Running);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(control_tree.tick(&mut hook), Status::Running);
+    assert!(hook.halted.is_empty());
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(control_tree.tick(&mut hook), Status::Failure);
+    assert_eq!(hook.halted, vec![leaf]);
+    assert_eq!(control_tree[leaf].status(), None);
+
+    // the next run gets the full duration again
+    assert_eq!(control_tree.tick(&mut hook), Status::Running);
+}
+
+/// A child that finishes in time keeps its status.
+#[test]
+fn timeout_in_time() {
+    use Status::*;
+    let clock = ManualClock::new();
+    let (mut control_tree, leaf) = timed_leaf(&clock, Duration::from_secs(2));
+    let mut hook = Scripted {
+        scripts: vec![(leaf, vec![Running, Success])],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(control_tree.tick(&mut hook), Success);
+    assert!(hook.halted.is_empty());
+}
+
+/// `root -> decorator -> leaf`, returns `(tree, leaf)`
+fn decorated_leaf(decorator: ControlNode) -> (ControlTree, CTreeNodeID) {
+    let mut control_tree = ControlTree::new();
+    let decorator = control_tree.add_child(ROOT_ID, decorator).unwrap();
+    let leaf = control_tree
+        .add_child(decorator, LeafNode::default())
+        .unwrap();
+    (control_tree, leaf)
+}
+
+/// [`StandardDecorator::RetryWithBackoff`] doesn't tick the failed child again until the backoff
+/// delay has passed, and the delay grows with each attempt.
+#[test]
+fn retry_with_backoff() {
+    use Status::*;
+    let clock = ManualClock::new();
+    let backoff = Backoff::Exponential {
+        initial: Duration::from_secs(1),
+        factor: 2,
+        max: Duration::from_secs(60),
+    };
+    let (mut control_tree, leaf) =
+        decorated_leaf(ControlNode::retry_with_backoff(2, backoff, clock.clone()));
+    let mut hook = Scripted {
+        scripts: vec![(leaf, vec![Failure, Failure, Success])],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(ticks_of(&hook.logger, leaf), 1);
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(ticks_of(&hook.logger, leaf), 2);
+
+    // second retry waits twice as long
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(ticks_of(&hook.logger, leaf), 2);
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(control_tree.tick(&mut hook), Success);
+    assert_eq!(ticks_of(&hook.logger, leaf), 3);
+}
+
+/// A backoff that saturates at [`Duration::MAX`] keeps waiting rather than overflowing, jitter
+/// included.
+#[test]
+fn retry_with_backoff_saturates() {
+    let clock = ManualClock::new();
+    clock.advance(Duration::from_secs(1));
+    let retry = RetryWithBackoff::new(1, Backoff::Fixed(Duration::MAX), clock.clone())
+        .with_jitter(Duration::from_secs(1), 7);
+    let (mut control_tree, _) = decorated_leaf(ControlNode::decorator(retry));
+    let mut hook = AlwaysFail::default();
+
+    assert_eq!(control_tree.tick(&mut hook), Status::Running);
+    let remaining =
+        control_tree
+            .iter_control_nodes()
+            .find_map(|node| match node.try_as_decorator() {
+                Some(StandardDecorator::RetryWithBackoff(retry)) => retry.remaining(),
+                _ => None,
+            });
+    assert!(remaining.is_some());
+}
+
+/// Out of retries, the failure goes through.
+#[test]
+fn retry_with_backoff_exhausted() {
+    let clock = ManualClock::new();
+    let backoff = Backoff::Fixed(Duration::from_millis(10));
+    let (mut control_tree, _) =
+        decorated_leaf(ControlNode::retry_with_backoff(1, backoff, clock.clone()));
+    let mut hook = AlwaysFail::default();
+
+    assert_eq!(control_tree.tick(&mut hook), Status::Running);
+    clock.advance(Duration::from_millis(10));
+    assert_eq!(control_tree.tick(&mut hook), Status::Failure);
+}
+
+/// [`StandardDecorator::Cooldown`] fails without running the child until the cooldown passes.
+#[test]
+fn cooldown() {
+    let clock = ManualClock::new();
+    let (mut control_tree, leaf) =
+        decorated_leaf(ControlNode::cooldown(Duration::from_secs(5), clock.clone()));
+    let mut hook = RunForever::default();
+
+    assert_eq!(control_tree.tick(&mut hook), Status::Success);
+    clock.advance(Duration::from_secs(4));
+    assert_eq!(control_tree.tick(&mut hook), Status::Failure);
+    assert_eq!(ticks_of(&hook.logger, leaf), 1);
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(control_tree.tick(&mut hook), Status::Success);
+    assert_eq!(ticks_of(&hook.logger, leaf), 2);
+}
+
+/// [`StandardDecorator::RepeatN`] resets & re-runs the child until it has succeeded `n` times.
+#[test]
+fn repeat_n() {
+    use Status::*;
+    let (mut control_tree, leaf) = decorated_leaf(ControlNode::repeat_n(3));
+    let mut hook = Scripted {
+        scripts: vec![(leaf, vec![Success])],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Success);
+    assert_eq!(ticks_of(&hook.logger, leaf), 3);
+
+    // any failure fails the loop
+    let mut hook = Scripted {
+        scripts: vec![(leaf, vec![Success, Failure])],
+        ..Default::default()
+    };
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+}
+
+/// [`StandardDecorator::RetryUntilSuccessful`] keeps re-running a failing child until it succeeds.
+#[test]
+fn retry_until_successful() {
+    use Status::*;
+    let (mut control_tree, leaf) = decorated_leaf(ControlNode::retry_until_successful());
+    let mut hook = Scripted {
+        scripts: vec![(leaf, vec![Failure, Failure, Running, Success])],
+        ..Default::default()
+    };
+
+    for _ in 0..3 {
+        assert_eq!(control_tree.tick(&mut hook), Running);
+    }
+    assert_eq!(control_tree.tick(&mut hook), Success);
+    assert_eq!(ticks_of(&hook.logger, leaf), 4);
+}
+
+/// [`StandardDecorator::KeepRunningUntilFailure`] keeps re-running a succeeding child until it
+/// fails.
+#[test]
+fn keep_running_until_failure() {
+    use Status::*;
+    let (mut control_tree, leaf) = decorated_leaf(ControlNode::keep_running_until_failure());
+    let mut hook = Scripted {
+        scripts: vec![(leaf, vec![Success, Success, Failure])],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+    assert_eq!(ticks_of(&hook.logger, leaf), 3);
+}
+
+/// [`StandardDecorator::Infinite`] only stops when it's halted.
+#[test]
+fn infinite() {
+    use Status::*;
+    let (mut control_tree, leaf) = decorated_leaf(ControlNode::infinite());
+    let mut hook = Scripted {
+        scripts: vec![(leaf, vec![Success, Failure, Success, Running])],
+        ..Default::default()
+    };
+
+    for _ in 0..5 {
+        assert_eq!(control_tree.tick(&mut hook), Running);
+    }
+    assert_eq!(ticks_of(&hook.logger, leaf), 5);
+    assert!(hook.halted.is_empty());
+
+    let infinite = control_tree.children(&ROOT_ID)[0];
+    control_tree.halt(infinite, &mut hook);
+    assert_eq!(hook.halted, vec![leaf]);
+}
+
+#[test]
+fn force_success_and_failure() {
+    use Status::*;
+    for (force, expect) in [
+        (ControlNode::force_success(), Success),
+        (ControlNode::force_failure(), Failure),
+    ] {
+        let (mut control_tree, leaf) = decorated_leaf(force);
+        for result in [Success, Failure] {
+            let mut hook = Scripted {
+                scripts: vec![(leaf, vec![Running, result])],
+                ..Default::default()
+            };
+            assert_eq!(control_tree.tick(&mut hook), Running);
+            assert_eq!(control_tree.tick(&mut hook), expect);
+        }
+    }
+}
+
+/// [`StandardDecorator::RunOnce`] keeps its result when its branch is reset, and only runs the
+/// child again once the whole tree is reset, unless it persists.
+#[test]
+fn run_once() {
+    use Status::*;
+    let mut control_tree = ControlTree::new();
+    let repeat = control_tree
+        .add_child(ROOT_ID, ControlNode::repeat_n(3))
+        .unwrap();
+    let once = control_tree
+        .add_child(repeat, ControlNode::run_once(RunOnce::default()))
+        .unwrap();
+    let leaf = control_tree.add_child(once, LeafNode::default()).unwrap();
+    let mut hook = Scripted {
+        scripts: vec![(leaf, vec![Success, Failure])],
+        ..Default::default()
+    };
+    assert_eq!(control_tree.run(&mut hook), Success);
+    assert_eq!(ticks_of(&hook.logger, leaf), 1);
+    assert_eq!(control_tree.run(&mut hook), Failure);
+    assert_eq!(ticks_of(&hook.logger, leaf), 2);
+
+    // the tree is reset every time it finishes, so without persisting the child runs every tick
+    let (mut control_tree, leaf) = decorated_leaf(ControlNode::run_once(RunOnce::default()));
+    let mut hook = Scripted {
+        scripts: vec![(leaf, vec![Failure, Success])],
+        ..Default::default()
+    };
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+    assert_eq!(control_tree.tick(&mut hook), Success);
+    assert_eq!(control_tree.tick(&mut hook), Success);
+    assert_eq!(ticks_of(&hook.logger, leaf), 3);
+
+    let (mut control_tree, leaf) =
+        decorated_leaf(ControlNode::run_once(RunOnce::default().persist()));
+    let mut hook = Scripted {
+        scripts: vec![(leaf, vec![Failure, Success])],
+        ..Default::default()
+    };
+    for _ in 0..3 {
+        assert_eq!(control_tree.tick(&mut hook), Failure);
+    }
+    assert_eq!(ticks_of(&hook.logger, leaf), 1);
+
+    let (mut control_tree, leaf) = decorated_leaf(ControlNode::run_once(
+        RunOnce::default().then(Success).persist(),
+    ));
+    let mut hook = Scripted {
+        scripts: vec![(leaf, vec![Failure])],
+        ..Default::default()
+    };
+    assert_eq!(control_tree.tick(&mut hook), Failure);
+    assert_eq!(control_tree.tick(&mut hook), Success);
+    assert_eq!(ticks_of(&hook.logger, leaf), 1);
+}
+
+/// Pass-through [`Decorator`] that logs its lifecycle.
+#[derive(Debug, Default, Clone)]
+struct Lifecycle {
+    events: Rc<RefCell<Vec<String>>>,
+    status: Option<Status>,
+}
+
+impl Lifecycle {
+    fn log(&self, event: impl Into<String>) {
+        self.events.borrow_mut().push(event.into());
+    }
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut self.events.borrow_mut())
+    }
+}
+
+impl Decorator for Lifecycle {
+    fn init(&mut self) {
+        self.log("init");
+    }
+    fn on_enter(&mut self) {
+        self.log("enter");
+    }
+    fn on_exit(&mut self, status: Status) {
+        self.log(format!("exit {status:?}"));
+    }
+    fn child_updated(&mut self, update: ChildUpdate) -> Status {
+        self.log(format!("child {:?}", update.status));
+        self.status = Some(update.status);
+        update.status
+    }
+    fn status(&self) -> Status {
+        self.status.unwrap_or_default()
+    }
+    fn reset(&mut self) {
+        self.log("reset");
+        self.status = None;
+    }
+    fn name(&self) -> String {
+        "Lifecycle".to_string()
+    }
+}
+
+/// `init` & `on_enter` on the first tick, `on_exit` once when the decorator finishes or is
+/// halted, always before `reset`.
+#[test]
+fn decorator_lifecycle() {
+    use Status::*;
+    let lifecycle = Lifecycle::default();
+    let mut control_tree = CTree::<Lifecycle>::new();
+    let decorator = control_tree
+        .add_child(ROOT_ID, CNode::decorator(lifecycle.clone()))
+        .unwrap();
+    let leaf = control_tree
+        .add_child(decorator, LeafNode::default())
+        .unwrap();
+    let mut hook = Scripted {
+        scripts: vec![(leaf, vec![Running, Success, Running])],
+        ..Default::default()
+    };
+
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(lifecycle.take(), vec!["init", "enter", "child Running"]);
+    assert_eq!(control_tree.tick(&mut hook), Success);
+    assert_eq!(lifecycle.take(), vec!["child Success", "exit Success"]);
+
+    // the next tick restarts the tree
+    assert_eq!(control_tree.tick(&mut hook), Running);
+    assert_eq!(
+        lifecycle.take(),
+        vec!["reset", "init", "enter", "child Running"]
+    );
+
+    control_tree.halt(decorator, &mut hook);
+    assert_eq!(lifecycle.take(), vec!["exit Running", "reset"]);
+}
+
+/// User defined [`Composite`] that goes with the majority of its children, so it needs an odd
+/// number of them.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Majority {
+    success: usize,
+    failure: usize,
+    status: Status,
+}
+
+impl Control for Majority {
+    fn tick(&mut self) -> Status {
+        self.status
+    }
+    fn child_updated(&mut self, update: ChildUpdate) {
+        match update.status {
+            Status::Success => self.success += 1,
+            Status::Failure => self.failure += 1,
+            Status::Running => {}
+        }
+    }
+    fn all_children_seen(&mut self) {
+        self.status = match self.success.cmp(&self.failure) {
+            std::cmp::Ordering::Greater => Status::Success,
+            std::cmp::Ordering::Less => Status::Failure,
+            std::cmp::Ordering::Equal => Status::Running,
+        };
+    }
+}
+
+impl Composite for Majority {
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+    fn name(&self) -> String {
+        "Majority".to_string()
+    }
+    fn validate(&self, children: &[CTreeNodeID]) -> Result<(), String> {
+        if children.len() % 2 == 1 {
+            Ok(())
+        } else {
+            Err("a majority needs an odd number of children".to_string())
+        }
+    }
+}
+
+impl GraphvizAttrs for Majority {
+    fn graphviz_attrs(&self) -> Vec<Attribute> {
+        vec![attr!("label", "\"majority\"")]
+    }
+}
+
+/// A user defined [`Composite`] gets built, validated, ticked & drawn like the standard ones.
+#[test]
+fn user_composite() {
+    let mut builder = CTree::<StandardDecorator, Majority>::builder();
+    builder.layer(|mut root_layer| {
+        root_layer.composite(Majority::default(), |mut majority_layer| {
+            for _ in 0..3 {
+                majority_layer.leaf_node(LeafNode::default());
+            }
+        })
+    });
+    let mut control_tree = builder.build().unwrap();
+
+    // leaves 2 & 4 fail, 3 succeeds
+    assert_eq!(
+        control_tree.run(&mut FailGiven::index_is_even()),
+        Status::Failure
+    );
+    control_tree.reset();
+    assert_eq!(
+        control_tree.run(&mut FailGiven::index_is_odd()),
+        Status::Success
+    );
+
+    let dot = control_tree
+        .graphviz_graph()
+        .print(&mut PrinterContext::default());
+    assert!(dot.contains("label=\"majority\""));
+    assert!(dot.contains("Majority (Succeeded)"));
+}
+
+#[test]
+fn user_composite_validation() {
+    let mut builder = CTree::<StandardDecorator, Majority>::builder();
+    builder.layer(|mut root_layer| {
+        root_layer.composite(Majority::default(), |mut majority_layer| {
+            majority_layer.leaf_node(LeafNode::default());
+            majority_layer.leaf_node(LeafNode::default());
+        })
+    });
+
+    let err = builder.build().unwrap_err();
+    assert!(matches!(
+        err,
+        ShrubberyError::InvalidComposite { composite, .. } if composite.index() == 1
+    ));
+}
+
+/// [`Rng`] that always rolls the highest value, so shuffles leave the order alone and weighted
+/// picks land on the last child.
+#[derive(Debug)]
+struct AlwaysHighest;
+
+impl Rng for AlwaysHighest {
+    fn next_u64(&mut self) -> u64 {
+        u64::MAX
+    }
+}
+
+fn always_fail() -> FailGiven {
+    FailGiven {
+        fail_fn: |_| Status::Failure,
+        logger: LeafLogger::default(),
+    }
+}
+
+/// A random selector tries every child once, in the order shuffled by its seed.
+#[test]
+fn random_selector() {
+    let (mut control_tree, leaves) =
+        parallel_leaves(ControlNode::random_selector(RngHandle::seeded(11)), 5);
+    let mut hook = always_fail();
+    assert_eq!(control_tree.tick(&mut hook), Status::Failure);
+
+    let mut order: Vec<usize> = (0..5).collect();
+    SplitMix64::new(11).shuffle(&mut order);
+    let tried: Vec<_> = hook.logger.updates.iter().map(|u| u.child_id).collect();
+    let expected: Vec<_> = order.iter().map(|&i| leaves[i]).collect();
+    assert_eq!(tried, expected);
+    assert_ne!(tried, leaves);
+
+    // the same seed makes the same choice
+    let (mut control_tree, _) =
+        parallel_leaves(ControlNode::random_selector(RngHandle::seeded(11)), 5);
+    let mut again = always_fail();
+    control_tree.tick(&mut again);
+    assert_eq!(again.logger.updates, hook.logger.updates);
+
+    // and a user provided rng is used as is
+    let (mut control_tree, leaves) =
+        parallel_leaves(ControlNode::random_selector(AlwaysHighest), 3);
+    let mut hook = always_fail();
+    control_tree.tick(&mut hook);
+    let tried: Vec<_> = hook.logger.updates.iter().map(|u| u.child_id).collect();
+    assert_eq!(tried, leaves);
+}
+
+/// [`StandardComposite::WeightedRandom`] only picks children with a weight, in proportion to it.
+#[test]
+fn weighted_random() {
+    let (mut control_tree, leaves) = parallel_leaves(
+        ControlNode::weighted_random(vec![0, 1, 0], SplitMix64::new(3)),
+        3,
+    );
+    let mut hook = LeafLogger::default();
+    assert_eq!(control_tree.tick(&mut hook), Status::Success);
+    assert_eq!(hook.updates.len(), 1);
+    assert_eq!(hook.updates[0].child_id, leaves[1]);
+
+    let (mut control_tree, leaves) = parallel_leaves(
+        ControlNode::weighted_random(vec![1, 1, 1], AlwaysHighest),
+        3,
+    );
+    let mut hook = LeafLogger::default();
+    control_tree.tick(&mut hook);
+    assert_eq!(hook.updates[0].child_id, leaves[2]);
+
+    // a weight is needed for every child
+    let mut builder = ControlTree::builder();
+    builder.layer(|mut root_layer| {
+        root_layer.weighted_random(vec![1, 1], AlwaysHighest, |mut weighted| {
+            weighted.leaf_node(LeafNode::default());
+        })
+    });
+    let err = builder.build().unwrap_err();
+    assert!(matches!(err, ShrubberyError::InvalidComposite { .. }));
+}
+
+/// All of the subtree is grafted below a [`StandardDecorator::Subtree`], including the leaves
+/// directly under its root, and each node is given its new id.
+#[test]
+fn add_subtree() {
+    let mut control_tree = ControlTree::new();
+    let first = control_tree
+        .add_child(ROOT_ID, LeafNode::default())
+        .unwrap();
+
+    let (mut subtree, subtree_order) = test_tree(ControlNode::sequence());
+    subtree.add_child(ROOT_ID, LeafNode::default()).unwrap();
+    control_tree.add_subtree_as_last_child(ROOT_ID, subtree);
+
+    let mut logger = LeafLogger::default();
+    assert_eq!(control_tree.run(&mut logger), Status::Success);
+    // every leaf of the subtree runs, after the first leaf
+    assert_eq!(logger.updates.len(), subtree_order.len() + 2);
+    assert_eq!(logger.updates[0].child_id, first);
+
+    for (_, children) in control_tree.iter_tree() {
+        for &child in children {
+            assert_eq!(control_tree[child].id(), Some(child));
+        }
+    }
+    let subtree = control_tree.children(&ROOT_ID)[1];
+    let subtree_root = control_tree.children(&subtree)[0];
+    assert_eq!(control_tree.children(&subtree_root).len(), 3);
+}